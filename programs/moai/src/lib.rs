@@ -1,15 +1,59 @@
-use anchor_lang::{prelude::*, system_program};
+use anchor_lang::{prelude::*, solana_program::program_option::COption, system_program};
 use anchor_spl::{
     associated_token::AssociatedToken,
     memo::{build_memo, BuildMemo, Memo},
+    metadata::{
+        create_metadata_accounts_v3, mpl_token_metadata::types::DataV2, CreateMetadataAccountsV3,
+        Metadata,
+    },
     token::{
-        approve_checked, mint_to, sync_native, transfer_checked, ApproveChecked, Mint, MintTo,
+        approve_checked, burn, mint_to, revoke, set_authority,
+        spl_token::{instruction::AuthorityType, native_mint},
+        sync_native, transfer_checked, ApproveChecked, Burn, Mint, MintTo, Revoke, SetAuthority,
         SyncNative, Token, TokenAccount, TransferChecked,
     },
 };
+use whirlpool_cpi::state::{
+    FeeTier, OpenPositionBumps, Position, Whirlpool, WhirlpoolBumps, WhirlpoolsConfig,
+};
 const LAMPORT: u64 = 1000000000;
-const ROCK_PRICE: u64 = 96000000;
-const FEE: u64 = 4000000;
+const MAX_ROCK_PRICE: u64 = 10 * LAMPORT;
+const MAX_FEE: u64 = LAMPORT / 10;
+const MAX_MOAI_REWARD: u64 = 1000 * LAMPORT;
+const MAX_MEME_COST: u64 = 100;
+const MAX_GROWTH_BPS: u16 = 1000;
+const MAX_REDEMPTION_FEE_BPS: u16 = 1000;
+pub const MAX_LEADERBOARD_SIZE: usize = 32;
+// A single slot has nothing to re-rank an unvoted leader against
+const MIN_LEADERBOARD_SIZE: u8 = 2;
+const BPS_DENOMINATOR: u128 = 10000;
+// Fixed point scale for the exponential curve
+const CURVE_SCALE: u128 = 1_000_000_000_000;
+// Graduated meme tokens have a fixed supply held by the moai
+const MEME_TOKEN_DECIMALS: u8 = 9;
+const MEME_TOKEN_SUPPLY: u64 = 1_000_000_000 * LAMPORT;
+const MAX_SYMBOL_LEN: usize = 10;
+const MAX_NAME_LEN: usize = 32;
+const MAX_URI_LEN: usize = 100;
+const MAX_REPORT_REASON_LEN: usize = 64;
+// Meme URIs must start with one of the moai's allowed prefixes
+const MAX_URI_PREFIXES: usize = 4;
+const MAX_URI_PREFIX_LEN: usize = 32;
+const DEFAULT_URI_PREFIXES: [&str; 2] = ["https://gateway.irys.xyz/", "ar://"];
+// Share of a graduated meme's supply paired with escrow WSOL in its whirlpool
+const MEME_POOL_ALLOCATION: u64 = MEME_TOKEN_SUPPLY / 2;
+// Whirlpool tick bounds and ticks per tick array
+const MAX_TICK_INDEX: i32 = 443636;
+const TICK_ARRAY_SIZE: i32 = 88;
+
+// Instruction flags, used as session scopes and pause bits
+pub const IX_CREATE_MEME: u8 = 1 << 0;
+pub const IX_VOTE: u8 = 1 << 1;
+pub const IX_MINT_ROCK: u8 = 1 << 2;
+pub const IX_UNVOTE: u8 = 1 << 3;
+pub const IX_REDEEM_ROCK: u8 = 1 << 4;
+const SESSION_SCOPES: u8 = IX_CREATE_MEME | IX_VOTE;
+const PAUSABLE_IXS: u8 = SESSION_SCOPES | IX_MINT_ROCK | IX_UNVOTE | IX_REDEEM_ROCK;
 
 pub fn amount_to_ui_amount_string(amount: u64, decimals: u8) -> String {
     let decimals = decimals as usize;
@@ -24,6 +68,98 @@ pub fn amount_to_ui_amount_string(amount: u64, decimals: u8) -> String {
     }
 }
 
+fn validate_meme_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        return Err(MoaiError::EmptyName.into());
+    }
+    if name.len() > MAX_NAME_LEN {
+        return Err(MoaiError::NameTooLong.into());
+    }
+    if name.chars().any(char::is_control) {
+        return Err(MoaiError::InvalidName.into());
+    }
+    Ok(())
+}
+
+fn validate_meme_uri(uri: &str, uri_prefixes: &[String]) -> Result<()> {
+    if uri.len() > MAX_URI_LEN {
+        return Err(MoaiError::UriTooLong.into());
+    }
+    if uri.chars().any(|c| c.is_control() || c.is_whitespace()) {
+        return Err(MoaiError::InvalidUri.into());
+    }
+    // The prefix alone does not point at any content
+    if !uri_prefixes
+        .iter()
+        .any(|prefix| uri.len() > prefix.len() && uri.starts_with(prefix.as_str()))
+    {
+        return Err(MoaiError::UriNotAllowed.into());
+    }
+    Ok(())
+}
+
+fn validate_uri_prefixes(uri_prefixes: &[String]) -> Result<()> {
+    if uri_prefixes.is_empty()
+        || uri_prefixes.len() > MAX_URI_PREFIXES
+        || uri_prefixes.iter().any(|prefix| {
+            prefix.is_empty()
+                || prefix.len() > MAX_URI_PREFIX_LEN
+                || prefix.chars().any(|c| c.is_control() || c.is_whitespace())
+        })
+    {
+        return Err(MoaiError::InvalidUriPrefixes.into());
+    }
+    Ok(())
+}
+
+// The spending wallet moves $ROCK as delegate of the owner's account
+fn check_delegated_amount(
+    rock_account: &TokenAccount,
+    user_spending: &Pubkey,
+    amount: u64,
+) -> Result<()> {
+    if rock_account.delegate != COption::Some(*user_spending)
+        || rock_account.delegated_amount < amount
+    {
+        return Err(MoaiError::InsufficientAllowance.into());
+    }
+    Ok(())
+}
+
+// Lowest and highest initializable ticks for a tick spacing
+fn full_range_ticks(tick_spacing: u16) -> (i32, i32) {
+    let tick_spacing = tick_spacing as i32;
+    let upper = MAX_TICK_INDEX / tick_spacing * tick_spacing;
+    (-upper, upper)
+}
+
+// Start index of the tick array holding `tick`
+fn tick_array_start_index(tick: i32, tick_spacing: u16) -> i32 {
+    let ticks_in_array = TICK_ARRAY_SIZE * tick_spacing as i32;
+    tick.div_euclid(ticks_in_array) * ticks_in_array
+}
+
+// Transfer out of a moai-owned token account
+fn transfer_from_moai<'info>(
+    token_program: AccountInfo<'info>,
+    accounts: TransferChecked<'info>,
+    signer_seeds: &[&[u8]],
+    amount: u64,
+    decimals: u8,
+) -> Result<()> {
+    if amount == 0 {
+        return Ok(());
+    }
+    transfer_checked(
+        CpiContext::new(token_program, accounts).with_signer(&[signer_seeds]),
+        amount,
+        decimals,
+    )
+}
+
+#[cfg(feature = "verifier")]
+pub mod verifier;
+
 declare_id!("2Svk2fb1YwpjKrxktUabBsYYm49HiXyxHpAAAK5g6K9t");
 
 #[program]
@@ -31,35 +167,149 @@ pub mod moai {
 
     use super::*;
 
-    pub fn initialize_moai(ctx: Context<InitializeMoai>) -> Result<()> {
+    pub fn initialize_moai(
+        ctx: Context<InitializeMoai>,
+        window: EpochWindow,
+        config: MoaiConfig,
+        pricing_mode: PricingMode,
+        voting_mode: VotingMode,
+    ) -> Result<()> {
+        window.validate()?;
+        config.validate()?;
+        pricing_mode.validate()?;
+
         let moai = &mut ctx.accounts.moai;
         moai.nonce = ctx.bumps.moai;
         moai.authority = *ctx.accounts.authority.key;
+        moai.seed_authority = *ctx.accounts.authority.key;
+        moai.pending_authority = None;
         moai.current_top_vote = None;
+        moai.current_top_vote_count = 0;
         moai.authority_valid = true;
         moai.epoch = 0;
         moai.escrow_account = *ctx.accounts.escrow_account.to_account_info().key;
         moai.moai_mint_account = *ctx.accounts.moai_mint.to_account_info().key;
         moai.rock_mint_account = *ctx.accounts.rock_mint.to_account_info().key;
+        moai.config = config;
+        moai.unclaimed_prizes = 0;
+        moai.meme_count = 0;
+        moai.uri_prefixes = DEFAULT_URI_PREFIXES
+            .iter()
+            .map(|prefix| prefix.to_string())
+            .collect();
+        moai.pricing_mode = pricing_mode;
+        moai.voting_mode = voting_mode;
+        moai.set_window(&window);
+
+        let mut leaderboard = ctx.accounts.leaderboard.load_init()?;
+        leaderboard.moai = moai.key();
+        leaderboard.epoch = moai.epoch;
+        leaderboard.capacity = config.leaderboard_size;
+
+        Ok(())
+    }
+
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+        ctx.accounts.moai.pending_authority = Some(new_authority);
+        msg!("Proposed {} as new authority", new_authority);
+
+        Ok(())
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let moai = &mut ctx.accounts.moai;
+        msg!(
+            "Authority transferred from {} to {}",
+            moai.authority,
+            ctx.accounts.new_authority.key()
+        );
+        moai.authority = ctx.accounts.new_authority.key();
+        moai.pending_authority = None;
+
+        Ok(())
+    }
+
+    pub fn renounce_authority(ctx: Context<RenounceAuthority>) -> Result<()> {
+        let moai = &mut ctx.accounts.moai;
+        moai.authority_valid = false;
+        moai.pending_authority = None;
+        moai.moderator = None;
+        // Nobody can seed pools any more, the budget rolls into the next prize
+        moai.liquidity_budget = 0;
+        msg!("Authority {} renounced", moai.authority);
+
+        Ok(())
+    }
+
+    pub fn set_uri_prefixes(ctx: Context<SetUriPrefixes>, uri_prefixes: Vec<String>) -> Result<()> {
+        validate_uri_prefixes(&uri_prefixes)?;
+
+        msg!("Allowed meme URI prefixes: {:?}", uri_prefixes);
+        ctx.accounts.moai.uri_prefixes = uri_prefixes;
+
+        Ok(())
+    }
+
+    pub fn set_moderator(ctx: Context<SetModerator>, moderator: Option<Pubkey>) -> Result<()> {
+        ctx.accounts.moai.moderator = moderator;
+        msg!("Moderator set to {:?}", moderator);
+
+        Ok(())
+    }
+
+    pub fn set_pause(ctx: Context<SetPause>, paused: bool, paused_ixs: u8) -> Result<()> {
+        if paused_ixs & !PAUSABLE_IXS != 0 {
+            return Err(MoaiError::InvalidPauseMask.into());
+        }
+
+        let moai = &mut ctx.accounts.moai;
+        moai.paused = paused;
+        moai.paused_ixs = paused_ixs;
+        msg!(
+            "Paused: {}, paused instructions: {:#07b}",
+            paused,
+            paused_ixs
+        );
 
         Ok(())
     }
 
     pub fn create_meme(
         ctx: Context<CreateMeme>,
-        _index: String,
         name: String,
         uri: String,
+        content_hash: [u8; 32],
     ) -> Result<()> {
         let signer_seeds: &[&[u8]] = &[
             b"moai".as_ref(),
-            ctx.accounts.moai.authority.as_ref(),
+            ctx.accounts.moai.seed_authority.as_ref(),
             &[ctx.accounts.moai.nonce],
         ];
 
-        if ctx.accounts.user_rock_account.amount < 1 {
+        ctx.accounts.moai.check_not_paused(IX_CREATE_MEME)?;
+        validate_meme_name(&name)?;
+        validate_meme_uri(&uri, &ctx.accounts.moai.uri_prefixes)?;
+        if content_hash == [0u8; 32] {
+            return Err(MoaiError::InvalidContentHash.into());
+        }
+
+        let clock = Clock::get()?;
+        ctx.accounts
+            .moai
+            .check_submission_window(clock.unix_timestamp)?;
+
+        let meme_cost = ctx.accounts.moai.config.meme_cost;
+        if ctx.accounts.user_rock_account.amount < meme_cost {
             return Err(MoaiError::NotEnoughRock.into());
         }
+        check_delegated_amount(
+            &ctx.accounts.user_rock_account,
+            ctx.accounts.user_spending.key,
+            meme_cost,
+        )?;
+        ctx.accounts
+            .session
+            .spend(IX_CREATE_MEME, clock.slot, meme_cost)?;
         transfer_checked(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
@@ -70,7 +320,7 @@ pub mod moai {
                     authority: ctx.accounts.user_spending.to_account_info(),
                 },
             ),
-            1,
+            meme_cost,
             0,
         )?;
 
@@ -79,52 +329,46 @@ pub mod moai {
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
                 MintTo {
-                    mint: ctx.accounts.rock_mint.to_account_info(),
-                    to: ctx.accounts.user_rock_account.to_account_info(),
+                    mint: ctx.accounts.moai_mint.to_account_info(),
+                    to: ctx.accounts.user_moai_account.to_account_info(),
                     authority: ctx.accounts.moai.to_account_info(),
                 },
             )
-            .with_signer(&[&signer_seeds[..]]),
-            1 * LAMPORT,
+            .with_signer(&[signer_seeds]),
+            ctx.accounts.moai.config.moai_reward,
         )?;
 
         let meme = &mut ctx.accounts.meme;
         meme.name = name;
         meme.uri = uri;
+        meme.content_hash = content_hash;
         meme.creator = *ctx.accounts.user_spending.key;
         meme.vote = 1;
+        meme.moai = ctx.accounts.moai.key();
+        meme.epoch = ctx.accounts.moai.epoch;
+        meme.id = ctx.accounts.moai.meme_count;
+        meme.bump = ctx.bumps.meme;
+        meme.creation_cost = meme_cost;
+        meme.last_vote_slot = clock.slot;
 
-        if ctx.accounts.moai.current_top_vote.is_none() {
-            ctx.accounts.moai.current_top_vote = Some(meme.to_account_info().key());
-            msg!("Current top vote is {}", meme.to_account_info().key());
-        } else {
-            if ctx.remaining_accounts.len() == 0 {
-                return Err(MoaiError::TopVoteNotProvided.into());
-            }
-            let top_vote_key = &mut ctx.accounts.moai.current_top_vote;
-            let top_vote_key = top_vote_key.unwrap();
-            let top_vote = ctx.remaining_accounts[0].to_account_info();
-            if top_vote_key != *top_vote.key {
-                return Err(MoaiError::TopVoteNotProvided.into());
-            }
-            if Meme::try_deserialize_unchecked(&mut &top_vote.data.borrow_mut()[..])
-                .unwrap()
-                .vote
-                <= meme.vote
-            {
-                ctx.accounts.moai.current_top_vote = Some(meme.to_account_info().key());
-                msg!("Current top vote is {}", meme.to_account_info().key());
-            }
-        }
+        let mut leaderboard = ctx.accounts.leaderboard.load_mut()?;
+        leaderboard.add(meme.key(), meme.vote, clock.slot);
+        ctx.accounts.moai.sync_top_vote(&leaderboard);
+        ctx.accounts.moai.meme_count += 1;
+        ctx.accounts.moai.epoch_rock_spent += meme_cost;
 
-        ctx.accounts.user_spending_vote.count += 1;
-        ctx.accounts.user_spending_vote.meme = meme.to_account_info().key();
-        ctx.accounts.user_spending_vote.user_spending =
-            ctx.accounts.user_spending.to_account_info().key();
+        // The creation cost stays out of rock_spent, so neither unvote nor a
+        // ban refund can return it
+        ctx.accounts.owner_vote.count += 1;
+        ctx.accounts.owner_vote.moai_minted += ctx.accounts.moai.config.moai_reward;
+        ctx.accounts.owner_vote.meme = meme.to_account_info().key();
+        ctx.accounts.owner_vote.owner = ctx.accounts.user_info.owner;
 
         let memo = format!(
-            "Created new meme : '{}' with 1 $ROCK & Vote to it creating 1 $MOAI",
-            meme.name
+            "Created new meme : '{}' with {} $ROCK & Vote to it creating {} $MOAI",
+            meme.name,
+            meme_cost,
+            amount_to_ui_amount_string(ctx.accounts.moai.config.moai_reward, 9)
         );
 
         // MEMO
@@ -136,15 +380,26 @@ pub mod moai {
         Ok(())
     }
 
-    pub fn mint_rock<'a>(ctx: Context<MintRock>, amount: u64) -> Result<()> {
+    pub fn mint_rock(ctx: Context<MintRock>, amount: u64) -> Result<()> {
         let signer_seeds: &[&[u8]] = &[
             b"moai".as_ref(),
-            ctx.accounts.moai.authority.as_ref(),
+            ctx.accounts.moai.seed_authority.as_ref(),
             &[ctx.accounts.moai.nonce],
         ];
 
-        ctx.accounts.user_info.rock_account = *ctx.accounts.user_rock_account.to_account_info().key;
-        ctx.accounts.user_info.moai_account = *ctx.accounts.user_moai_account.to_account_info().key;
+        ctx.accounts.moai.check_not_paused(IX_MINT_ROCK)?;
+        ctx.accounts
+            .moai
+            .check_sale_window(Clock::get()?.unix_timestamp)?;
+
+        let user_info = &mut ctx.accounts.user_info;
+        if user_info.owner == Pubkey::default() {
+            user_info.owner = ctx.accounts.user.key();
+        } else if user_info.owner != ctx.accounts.user.key() {
+            return Err(MoaiError::Unauthorized.into());
+        }
+        user_info.rock_account = *ctx.accounts.user_rock_account.to_account_info().key;
+        user_info.moai_account = *ctx.accounts.user_moai_account.to_account_info().key;
 
         // Transfer Sol to Escrow
         let sol_transfer_context = CpiContext::new(
@@ -155,7 +410,13 @@ pub mod moai {
             },
         );
 
-        let sol_transfer_amount_rock = amount * ROCK_PRICE; // 1 ROCK = 0.099 SOL
+        let sol_transfer_amount_rock = ctx.accounts.moai.pricing_mode.buy_cost(
+            ctx.accounts.moai.config.rock_price,
+            ctx.accounts
+                .moai
+                .curve_supply(ctx.accounts.rock_mint.supply),
+            amount,
+        )?;
         system_program::transfer(sol_transfer_context, sol_transfer_amount_rock)?;
 
         sync_native(
@@ -165,7 +426,7 @@ pub mod moai {
                     account: ctx.accounts.escrow_account.to_account_info(),
                 },
             )
-            .with_signer(&[&signer_seeds[..]]),
+            .with_signer(&[signer_seeds]),
         )?;
 
         // Transfer Sol to Spending Wallet
@@ -177,7 +438,9 @@ pub mod moai {
             },
         );
 
-        let sol_transfer_amount_fee = amount * FEE; // FEE = 0.001 SOL / ROCK
+        let sol_transfer_amount_fee = amount
+            .checked_mul(ctx.accounts.moai.config.fee)
+            .ok_or(MoaiError::MathOverflow)?;
         system_program::transfer(sol_transfer_context, sol_transfer_amount_fee)?;
 
         // Mint ROCK
@@ -190,25 +453,142 @@ pub mod moai {
                     authority: ctx.accounts.moai.to_account_info(),
                 },
             )
-            .with_signer(&[&signer_seeds[..]]),
+            .with_signer(&[signer_seeds]),
             amount,
         )?;
 
-        approve_checked(
+        // Minting no longer makes the spending wallet a $ROCK delegate. Clients
+        // that went straight from mint_rock to vote must call create_session first.
+        let memo = format!("Minted {} $ROCK with {} SOL & keep {} SOL to spending wallet ({}) for tx fee in future. You can withdraw SOL in spending wallet any time", amount, amount_to_ui_amount_string(sol_transfer_amount_rock, 9),  amount_to_ui_amount_string(sol_transfer_amount_fee, 9), ctx.accounts.user_spending.to_account_info().key);
+
+        // MEMO
+        build_memo(
+            CpiContext::new(ctx.accounts.memo_program.to_account_info(), BuildMemo {}),
+            memo.as_bytes(),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn redeem_rock(ctx: Context<RedeemRock>, amount: u64) -> Result<()> {
+        let signer_seeds: &[&[u8]] = &[
+            b"moai".as_ref(),
+            ctx.accounts.moai.seed_authority.as_ref(),
+            &[ctx.accounts.moai.nonce],
+        ];
+
+        ctx.accounts.moai.check_not_paused(IX_REDEEM_ROCK)?;
+        ctx.accounts
+            .moai
+            .check_redemption_window(Clock::get()?.unix_timestamp)?;
+
+        if amount == 0 || ctx.accounts.user_rock_account.amount < amount {
+            return Err(MoaiError::NotEnoughRock.into());
+        }
+
+        let supply = ctx
+            .accounts
+            .moai
+            .curve_supply(ctx.accounts.rock_mint.supply);
+        let sol_curve_amount = ctx.accounts.moai.pricing_mode.sell_refund(
+            ctx.accounts.moai.config.rock_price,
+            supply - amount,
+            amount,
+        )?;
+        // Redemption fee stays in the escrow
+        let sol_fee_amount = (sol_curve_amount as u128
+            * ctx.accounts.moai.config.redemption_fee_bps as u128
+            / BPS_DENOMINATOR) as u64;
+        let sol_refund_amount = sol_curve_amount - sol_fee_amount;
+        // Prizes owed to past epochs and the liquidity budget are not redeemable
+        let escrow_available = ctx
+            .accounts
+            .escrow_account
+            .amount
+            .saturating_sub(ctx.accounts.moai.escrow_reserved());
+        if escrow_available < sol_refund_amount {
+            return Err(MoaiError::InsufficientEscrow.into());
+        }
+
+        // Burn ROCK
+        burn(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
-                ApproveChecked {
-                    to: ctx.accounts.user_rock_account.to_account_info(),
+                Burn {
                     mint: ctx.accounts.rock_mint.to_account_info(),
+                    from: ctx.accounts.user_rock_account.to_account_info(),
                     authority: ctx.accounts.user.to_account_info(),
-                    delegate: ctx.accounts.user_spending.to_account_info(),
                 },
             ),
             amount,
-            0,
         )?;
 
-        let memo = format!("Minted {} $ROCK with {} SOL & keep {} SOL to spending wallet ({}) for tx fee in future. You can withdraw SOL in spending wallet any time", amount, amount_to_ui_amount_string(sol_transfer_amount_rock, 9),  amount_to_ui_amount_string(sol_transfer_amount_fee, 9), ctx.accounts.user_spending.to_account_info().key);
+        // Return WSOL from Escrow
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.escrow_account.to_account_info(),
+                    to: ctx.accounts.user_wsol_account.to_account_info(),
+                    mint: ctx.accounts.wsol_mint.to_account_info(),
+                    authority: ctx.accounts.moai.to_account_info(),
+                },
+            )
+            .with_signer(&[signer_seeds]),
+            sol_refund_amount,
+            9,
+        )?;
+
+        let memo = format!(
+            "Redeemed {} $ROCK for {} SOL ({} SOL redemption fee)",
+            amount,
+            amount_to_ui_amount_string(sol_refund_amount, 9),
+            amount_to_ui_amount_string(sol_fee_amount, 9)
+        );
+
+        // MEMO
+        build_memo(
+            CpiContext::new(ctx.accounts.memo_program.to_account_info(), BuildMemo {}),
+            memo.as_bytes(),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn close_spending_session(ctx: Context<CloseSpendingSession>) -> Result<()> {
+        // Leave a delegate approved for another session in place
+        if ctx.accounts.user_rock_account.delegate
+            == COption::Some(ctx.accounts.user_spending.key())
+        {
+            revoke(CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Revoke {
+                    source: ctx.accounts.user_rock_account.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ))?;
+        }
+
+        // Return remaining fee float in Spending Wallet
+        let sol_withdraw_amount = ctx.accounts.user_spending.lamports();
+        if sol_withdraw_amount > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.user_spending.to_account_info(),
+                        to: ctx.accounts.user.to_account_info(),
+                    },
+                ),
+                sol_withdraw_amount,
+            )?;
+        }
+
+        let memo = format!(
+            "Closed spending wallet ({}) & withdrew {} SOL",
+            ctx.accounts.user_spending.key(),
+            amount_to_ui_amount_string(sol_withdraw_amount, 9)
+        );
 
         // MEMO
         build_memo(
@@ -219,16 +599,107 @@ pub mod moai {
         Ok(())
     }
 
-    pub fn vote(ctx: Context<Vote>) -> Result<()> {
+    pub fn create_session(
+        ctx: Context<CreateSession>,
+        expiry_slot: u64,
+        max_rock_spend: u64,
+        scope: u8,
+    ) -> Result<()> {
+        if expiry_slot <= Clock::get()?.slot || scope == 0 || scope & !SESSION_SCOPES != 0 {
+            return Err(MoaiError::InvalidSession.into());
+        }
+
+        let session = &mut ctx.accounts.session;
+        // A token account has a single delegate, so a new key replaces the old session
+        if session.user_spending != Pubkey::default()
+            && session.user_spending != ctx.accounts.user_spending.key()
+        {
+            msg!("Session for {} replaced", session.user_spending);
+        }
+        session.owner = ctx.accounts.user.key();
+        session.user_spending = ctx.accounts.user_spending.key();
+        session.expiry_slot = expiry_slot;
+        session.max_rock_spend = max_rock_spend;
+        session.rock_spent = 0;
+        session.scope = scope;
+
+        approve_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                ApproveChecked {
+                    to: ctx.accounts.user_rock_account.to_account_info(),
+                    mint: ctx.accounts.rock_mint.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                    delegate: ctx.accounts.user_spending.to_account_info(),
+                },
+            ),
+            max_rock_spend,
+            0,
+        )?;
+
+        msg!(
+            "Session for {} expires at slot {} with {} $ROCK allowance",
+            session.user_spending,
+            expiry_slot,
+            max_rock_spend
+        );
+
+        Ok(())
+    }
+
+    pub fn revoke_session(ctx: Context<RevokeSession>) -> Result<()> {
+        revoke(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Revoke {
+                source: ctx.accounts.user_rock_account.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ))?;
+
+        msg!("Session for {} revoked", ctx.accounts.session.user_spending);
+
+        Ok(())
+    }
+
+    pub fn vote(ctx: Context<Vote>, amount: u64) -> Result<()> {
         let signer_seeds: &[&[u8]] = &[
             b"moai".as_ref(),
-            ctx.accounts.moai.authority.as_ref(),
+            ctx.accounts.moai.seed_authority.as_ref(),
             &[ctx.accounts.moai.nonce],
         ];
 
-        if ctx.accounts.user_rock_account.amount < 1 {
+        ctx.accounts.moai.check_not_paused(IX_VOTE)?;
+
+        let clock = Clock::get()?;
+        ctx.accounts
+            .moai
+            .check_voting_window(clock.unix_timestamp)?;
+
+        if amount == 0 {
+            return Err(MoaiError::InvalidVoteAmount.into());
+        }
+        let rock_cost = ctx
+            .accounts
+            .moai
+            .voting_mode
+            .rock_cost(ctx.accounts.owner_vote.count, amount)?;
+        if ctx.accounts.user_rock_account.amount < rock_cost {
             return Err(MoaiError::NotEnoughRock.into());
         }
+        check_delegated_amount(
+            &ctx.accounts.user_rock_account,
+            ctx.accounts.user_spending.key,
+            rock_cost,
+        )?;
+        ctx.accounts.session.spend(IX_VOTE, clock.slot, rock_cost)?;
+        let moai_reward = ctx
+            .accounts
+            .moai
+            .config
+            .moai_reward
+            .checked_mul(amount)
+            .ok_or(MoaiError::MathOverflow)?;
+
         transfer_checked(
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
@@ -239,7 +710,7 @@ pub mod moai {
                     authority: ctx.accounts.user_spending.to_account_info(),
                 },
             ),
-            1,
+            rock_cost,
             0,
         )?;
 
@@ -248,53 +719,34 @@ pub mod moai {
             CpiContext::new(
                 ctx.accounts.token_program.to_account_info(),
                 MintTo {
-                    mint: ctx.accounts.rock_mint.to_account_info(),
-                    to: ctx.accounts.user_rock_account.to_account_info(),
+                    mint: ctx.accounts.moai_mint.to_account_info(),
+                    to: ctx.accounts.user_moai_account.to_account_info(),
                     authority: ctx.accounts.moai.to_account_info(),
                 },
             )
-            .with_signer(&[&signer_seeds[..]]),
-            1 * LAMPORT,
+            .with_signer(&[signer_seeds]),
+            moai_reward,
         )?;
-        ctx.accounts.meme.vote += 1;
-        ctx.accounts.user_spending_vote.count += 1;
-        ctx.accounts.user_spending_vote.meme = *ctx.accounts.meme.to_account_info().key;
-        ctx.accounts.user_spending_vote.user_spending =
-            *ctx.accounts.user_spending.to_account_info().key;
+        ctx.accounts.meme.vote += amount;
+        ctx.accounts.owner_vote.count += amount;
+        ctx.accounts.owner_vote.rock_spent += rock_cost;
+        ctx.accounts.owner_vote.moai_minted += moai_reward;
+        ctx.accounts.owner_vote.meme = *ctx.accounts.meme.to_account_info().key;
+        ctx.accounts.owner_vote.owner = ctx.accounts.user_info.owner;
+        ctx.accounts.meme.rock_voted += rock_cost;
+        ctx.accounts.meme.last_vote_slot = clock.slot;
+        ctx.accounts.moai.epoch_rock_spent += rock_cost;
 
-        if ctx.accounts.moai.current_top_vote.is_none() {
-            ctx.accounts.moai.current_top_vote = Some(ctx.accounts.meme.to_account_info().key());
-            msg!(
-                "Current top vote is {}",
-                ctx.accounts.meme.to_account_info().key()
-            );
-        } else {
-            if ctx.remaining_accounts.len() == 0 {
-                return Err(MoaiError::TopVoteNotProvided.into());
-            }
-            let top_vote_key = &mut ctx.accounts.moai.current_top_vote;
-            let top_vote_key = top_vote_key.unwrap();
-            let top_vote = ctx.remaining_accounts[0].to_account_info();
-            if top_vote_key != *top_vote.key {
-                return Err(MoaiError::TopVoteNotProvided.into());
-            }
-            if Meme::try_deserialize_unchecked(&mut &top_vote.data.borrow_mut()[..])
-                .unwrap()
-                .vote
-                <= ctx.accounts.meme.vote
-            {
-                ctx.accounts.moai.current_top_vote =
-                    Some(ctx.accounts.meme.to_account_info().key());
-                msg!(
-                    "Current top vote is {}",
-                    ctx.accounts.meme.to_account_info().key()
-                );
-            }
-        }
+        let mut leaderboard = ctx.accounts.leaderboard.load_mut()?;
+        leaderboard.record(ctx.accounts.meme.key(), ctx.accounts.meme.vote, clock.slot);
+        ctx.accounts.moai.sync_top_vote(&leaderboard);
 
         let memo = format!(
-            "Vote to meme : '{}' with 1 $ROCK & created 1 $MOAI",
-            ctx.accounts.meme.name
+            "Vote {} to meme : '{}' with {} $ROCK & created {} $MOAI",
+            amount,
+            ctx.accounts.meme.name,
+            rock_cost,
+            amount_to_ui_amount_string(moai_reward, 9)
         );
 
         // MEMO
@@ -305,144 +757,1988 @@ pub mod moai {
 
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-pub struct InitializeMoai<'info> {
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    #[account(init, payer=authority, space= 8+Moai::INIT_SPACE, seeds=[b"moai".as_ref(), authority.key().as_ref()], bump)]
-    pub moai: Account<'info, Moai>,
-    #[account(init, payer=authority, associated_token::mint=wsol_mint, associated_token::authority=moai)]
-    pub escrow_account: Account<'info, TokenAccount>,
-    #[account(mint::decimals = 9)]
-    pub wsol_mint: Account<'info, Mint>,
-    #[account(init, payer=authority, mint::authority=moai, mint::decimals=9, mint::freeze_authority=moai)]
-    pub moai_mint: Account<'info, Mint>,
-    #[account(init, payer=authority, mint::authority=moai, mint::decimals=0, mint::freeze_authority=moai)]
-    pub rock_mint: Account<'info, Mint>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-}
+    pub fn unvote(ctx: Context<Unvote>, amount: u64) -> Result<()> {
+        let moai_key = ctx.accounts.moai.key();
+        let epoch_bytes = ctx.accounts.meme.epoch.to_le_bytes();
+        let id_bytes = ctx.accounts.meme.id.to_le_bytes();
+        let meme_signer_seeds: &[&[u8]] = &[
+            b"meme".as_ref(),
+            moai_key.as_ref(),
+            epoch_bytes.as_ref(),
+            id_bytes.as_ref(),
+            &[ctx.accounts.meme.bump],
+        ];
 
-#[derive(Accounts)]
-#[instruction(index: String)]
-pub struct CreateMeme<'info> {
-    #[account(mut)]
-    pub user_spending: Signer<'info>,
-    #[account(init, payer = user_spending, space =8+Meme::INIT_SPACE , seeds=[b"meme".as_ref(), index.as_bytes()], bump)]
-    pub meme: Account<'info, Meme>,
-    #[account(mut)]
-    pub moai: Account<'info, Moai>,
-    #[account(mut, mint::decimals = 0, mint::authority = moai)]
-    pub rock_mint: Account<'info, Mint>,
-    #[account(mut, mint::decimals = 9, mint::authority = moai)]
-    pub moai_mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub user_rock_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub user_moai_account: Account<'info, TokenAccount>,
-    #[account(init_if_needed, payer=user_spending, seeds=[b"vote".as_ref(), user_spending.key().as_ref(),meme.key().as_ref()], bump, space=8+VoteStatus::INIT_SPACE)]
-    pub user_spending_vote: Account<'info, VoteStatus>,
-    #[account(init, payer=user_spending, associated_token::mint = rock_mint, associated_token::authority = meme)]
-    pub meme_rock_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub memo_program: Program<'info, Memo>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-}
+        ctx.accounts.moai.check_not_paused(IX_UNVOTE)?;
 
-#[derive(Accounts)]
-pub struct MintRock<'info> {
-    #[account(mut)]
-    pub user: Signer<'info>,
-    #[account(mut)]
-    pub user_spending: Signer<'info>,
-    #[account(init_if_needed, payer=user, seeds=[b"user".as_ref(), moai.key().as_ref(), user_spending.key().as_ref()], bump, space=8+User::INIT_SPACE)]
-    pub user_info: Account<'info, User>,
-    pub moai: Account<'info, Moai>,
-    #[account(mut)]
-    pub rock_mint: Account<'info, Mint>,
-    #[account(mut)]
-    pub moai_mint: Account<'info, Mint>,
-    #[account(init_if_needed, payer=user, associated_token::mint = rock_mint, associated_token::authority = user)]
-    pub user_rock_account: Account<'info, TokenAccount>,
-    #[account(init_if_needed, payer=user, associated_token::mint = moai_mint, associated_token::authority = user)]
-    pub user_moai_account: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub escrow_account: Account<'info, TokenAccount>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
-    pub memo_program: Program<'info, Memo>,
-    pub rent: Sysvar<'info, Rent>,
-}
+        let clock = Clock::get()?;
+        ctx.accounts
+            .moai
+            .check_voting_window(clock.unix_timestamp)?;
 
-#[derive(Accounts)]
-pub struct Vote<'info> {
-    #[account(mut)]
-    pub user_spending: Signer<'info>,
-    #[account(mut)]
+        let vote_status = &ctx.accounts.owner_vote;
+        if amount == 0 || vote_status.count < amount {
+            return Err(MoaiError::InvalidVoteAmount.into());
+        }
+        let rock_refund = ctx
+            .accounts
+            .moai
+            .voting_mode
+            .rock_cost(vote_status.count - amount, amount)?
+            .min(vote_status.rock_spent);
+        // Claw back the share of what was actually minted, whatever the
+        // reward is now
+        let moai_clawback =
+            (vote_status.moai_minted as u128 * amount as u128 / vote_status.count as u128) as u64;
+        if ctx.accounts.user_moai_account.amount < moai_clawback {
+            return Err(MoaiError::NotEnoughMoai.into());
+        }
+
+        // Return ROCK from Meme
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.meme_rock_account.to_account_info(),
+                    to: ctx.accounts.user_rock_account.to_account_info(),
+                    mint: ctx.accounts.rock_mint.to_account_info(),
+                    authority: ctx.accounts.meme.to_account_info(),
+                },
+            )
+            .with_signer(&[meme_signer_seeds]),
+            rock_refund,
+            0,
+        )?;
+
+        // Burn Moai
+        burn(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Burn {
+                    mint: ctx.accounts.moai_mint.to_account_info(),
+                    from: ctx.accounts.user_moai_account.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ),
+            moai_clawback,
+        )?;
+
+        ctx.accounts.meme.vote -= amount;
+        ctx.accounts.owner_vote.count -= amount;
+        ctx.accounts.owner_vote.rock_spent -= rock_refund;
+        ctx.accounts.owner_vote.moai_minted -= moai_clawback;
+        ctx.accounts.meme.rock_voted -= rock_refund;
+        ctx.accounts.meme.last_vote_slot = clock.slot;
+        ctx.accounts.moai.epoch_rock_spent -= rock_refund;
+
+        let mut leaderboard = ctx.accounts.leaderboard.load_mut()?;
+        leaderboard.check_unvote(ctx.accounts.meme.key(), ctx.accounts.meme.vote, clock.slot)?;
+        leaderboard.record(ctx.accounts.meme.key(), ctx.accounts.meme.vote, clock.slot);
+        ctx.accounts.moai.sync_top_vote(&leaderboard);
+
+        let memo = format!(
+            "Unvote {} from meme : '{}', returned {} $ROCK & burned {} $MOAI",
+            amount,
+            ctx.accounts.meme.name,
+            rock_refund,
+            amount_to_ui_amount_string(moai_clawback, 9)
+        );
+
+        // MEMO
+        build_memo(
+            CpiContext::new(ctx.accounts.memo_program.to_account_info(), BuildMemo {}),
+            memo.as_bytes(),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn report_meme(ctx: Context<ReportMeme>, reason: String) -> Result<()> {
+        if reason.trim().is_empty() || reason.len() > MAX_REPORT_REASON_LEN {
+            return Err(MoaiError::InvalidReportReason.into());
+        }
+
+        let report = &mut ctx.accounts.report;
+        report.meme = ctx.accounts.meme.key();
+        report.reporter = ctx.accounts.reporter.key();
+        report.reason = reason;
+        report.slot = Clock::get()?.slot;
+
+        let meme = &mut ctx.accounts.meme;
+        meme.report_count += 1;
+        msg!(
+            "Meme {} reported by {}, {} reports",
+            meme.key(),
+            report.reporter,
+            meme.report_count
+        );
+
+        Ok(())
+    }
+
+    pub fn moderate_meme(ctx: Context<ModerateMeme>, status: MemeStatus) -> Result<()> {
+        let meme = &mut ctx.accounts.meme;
+        // Past epochs already paid out of the escrow, a ban would refund it twice
+        if status == MemeStatus::Banned && meme.epoch != ctx.accounts.moai.epoch {
+            return Err(MoaiError::EpochClosed.into());
+        }
+        meme.status = status;
+        msg!("Meme {} set to {:?}", meme.key(), status);
+
+        // A banned meme can no longer win the running epoch, and the $ROCK its
+        // voters can claim back is no longer spent. The meme's token account
+        // also holds whatever anyone sent it, so go by the recorded votes.
+        if status == MemeStatus::Banned {
+            ctx.accounts.moai.epoch_rock_spent -= meme.rock_voted;
+
+            let mut leaderboard = ctx.accounts.leaderboard.load_mut()?;
+            leaderboard.remove(meme.key());
+            ctx.accounts.moai.sync_top_vote(&leaderboard);
+        }
+
+        Ok(())
+    }
+
+    // Anyone may put an unranked meme back in its place, for when a ban or an
+    // unvote left the board behind
+    pub fn rerank_meme(ctx: Context<RerankMeme>) -> Result<()> {
+        let meme = &ctx.accounts.meme;
+        let mut leaderboard = ctx.accounts.leaderboard.load_mut()?;
+        leaderboard.record(meme.key(), meme.vote, meme.last_vote_slot);
+        ctx.accounts.moai.sync_top_vote(&leaderboard);
+
+        Ok(())
+    }
+
+    pub fn claim_ban_refund(ctx: Context<ClaimBanRefund>) -> Result<()> {
+        let moai_key = ctx.accounts.moai.key();
+        let epoch_bytes = ctx.accounts.meme.epoch.to_le_bytes();
+        let id_bytes = ctx.accounts.meme.id.to_le_bytes();
+        let meme_signer_seeds: &[&[u8]] = &[
+            b"meme".as_ref(),
+            moai_key.as_ref(),
+            epoch_bytes.as_ref(),
+            id_bytes.as_ref(),
+            &[ctx.accounts.meme.bump],
+        ];
+
+        let vote_status = &ctx.accounts.owner_vote;
+        let rock_refund = vote_status.rock_spent;
+        if rock_refund == 0 {
+            return Err(MoaiError::NothingToRefund.into());
+        }
+
+        // Return ROCK from Meme, the $MOAI minted for the votes is kept
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.meme_rock_account.to_account_info(),
+                    to: ctx.accounts.user_rock_account.to_account_info(),
+                    mint: ctx.accounts.rock_mint.to_account_info(),
+                    authority: ctx.accounts.meme.to_account_info(),
+                },
+            )
+            .with_signer(&[meme_signer_seeds]),
+            rock_refund,
+            0,
+        )?;
+
+        ctx.accounts.meme.vote = ctx.accounts.meme.vote.saturating_sub(vote_status.count);
+        ctx.accounts.owner_vote.count = 0;
+        ctx.accounts.owner_vote.rock_spent = 0;
+        ctx.accounts.owner_vote.moai_minted = 0;
+
+        let memo = format!(
+            "Refunded {} $ROCK from banned meme : '{}'",
+            rock_refund, ctx.accounts.meme.name
+        );
+
+        // MEMO
+        build_memo(
+            CpiContext::new(ctx.accounts.memo_program.to_account_info(), BuildMemo {}),
+            memo.as_bytes(),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn end_epoch(
+        ctx: Context<EndEpoch>,
+        next_window: EpochWindow,
+        next_voting_mode: VotingMode,
+    ) -> Result<()> {
+        let moai = &mut ctx.accounts.moai;
+        let epoch_result = &mut ctx.accounts.epoch_result;
+
+        let now = Clock::get()?.unix_timestamp;
+        if now < moai.epoch_end_ts {
+            return Err(MoaiError::EpochNotEnded.into());
+        }
+        // Without an authority the caller cannot pick the schedule, the last
+        // epoch repeats from now
+        let (next_window, next_voting_mode) = if moai.authority_valid {
+            (next_window, next_voting_mode)
+        } else {
+            (moai.repeat_window(now), moai.voting_mode)
+        };
+        next_window.validate()?;
+        if next_window.start_ts < moai.epoch_end_ts {
+            return Err(MoaiError::InvalidEpochWindow.into());
+        }
+        // Bans emptied the board, the best remaining meme has to be reranked
+        if ctx.accounts.leaderboard.load()?.needs_rerank() {
+            return Err(MoaiError::LeaderboardIncomplete.into());
+        }
+
+        epoch_result.epoch = moai.epoch;
+        epoch_result.start_ts = moai.epoch_start_ts;
+        epoch_result.end_ts = moai.epoch_end_ts;
+        epoch_result.voting_mode = moai.voting_mode;
+        epoch_result.winner = moai.current_top_vote;
+        epoch_result.winner_vote = moai.current_top_vote_count;
+        epoch_result.meme_count = moai.meme_count;
+        epoch_result.creator_share_bps = moai.config.creator_share_bps;
+        epoch_result.voter_share_bps = moai.config.voter_share_bps;
+        epoch_result.treasury_share_bps = moai.config.treasury_share_bps;
+        epoch_result.liquidity_share_bps = moai.config.liquidity_share_bps;
+
+        // $ROCK spent into memes leaves the curve, freeing the SOL behind it.
+        // Without a winner that SOL rolls over to the next prize.
+        moai.rock_retired += moai.epoch_rock_spent;
+        moai.epoch_rock_spent = 0;
+
+        if let Some(top_vote_key) = moai.current_top_vote {
+            // The escrow beyond what is owed and what backs the $ROCK still
+            // redeemable on the curve becomes this epoch's prize
+            let curve_reserve = moai.pricing_mode.sell_refund(
+                moai.config.rock_price,
+                0,
+                moai.curve_supply(ctx.accounts.rock_mint.supply),
+            )?;
+            epoch_result.prize_pool = ctx
+                .accounts
+                .escrow_account
+                .amount
+                .saturating_sub(moai.escrow_reserved())
+                .saturating_sub(curve_reserve);
+            moai.unclaimed_prizes += epoch_result.prize_pool;
+            msg!(
+                "Epoch {} winner is {} with {} votes, prize pool {} SOL",
+                moai.epoch,
+                top_vote_key,
+                moai.current_top_vote_count,
+                amount_to_ui_amount_string(epoch_result.prize_pool, 9)
+            );
+        } else {
+            msg!("Epoch {} ended without any meme", moai.epoch);
+        }
+
+        moai.epoch += 1;
+        moai.current_top_vote = None;
+        moai.current_top_vote_count = 0;
+        moai.meme_count = 0;
+        moai.set_window(&next_window);
+        moai.voting_mode = next_voting_mode;
+
+        let mut leaderboard = ctx.accounts.next_leaderboard.load_init()?;
+        leaderboard.moai = moai.key();
+        leaderboard.epoch = moai.epoch;
+        leaderboard.capacity = moai.config.leaderboard_size;
+
+        Ok(())
+    }
+
+    pub fn distribute_epoch(ctx: Context<DistributeEpoch>) -> Result<()> {
+        let signer_seeds: &[&[u8]] = &[
+            b"moai".as_ref(),
+            ctx.accounts.moai.seed_authority.as_ref(),
+            &[ctx.accounts.moai.nonce],
+        ];
+
+        // Shares as they were when the epoch ended
+        let epoch_result = &ctx.accounts.epoch_result;
+        let prize_pool = epoch_result.prize_pool;
+        let creator_amount =
+            (prize_pool as u128 * epoch_result.creator_share_bps as u128 / BPS_DENOMINATOR) as u64;
+        let treasury_amount =
+            (prize_pool as u128 * epoch_result.treasury_share_bps as u128 / BPS_DENOMINATOR) as u64;
+        // Only the authority can seed pools, without one the share goes to voters
+        let liquidity_amount = if ctx.accounts.moai.authority_valid {
+            (prize_pool as u128 * epoch_result.liquidity_share_bps as u128 / BPS_DENOMINATOR) as u64
+        } else {
+            0
+        };
+        // Rounding dust stays with the voters
+        let voter_pool = prize_pool - creator_amount - treasury_amount - liquidity_amount;
+
+        // Creator share from Escrow
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.escrow_account.to_account_info(),
+                    to: ctx.accounts.creator_wsol_account.to_account_info(),
+                    mint: ctx.accounts.wsol_mint.to_account_info(),
+                    authority: ctx.accounts.moai.to_account_info(),
+                },
+            )
+            .with_signer(&[signer_seeds]),
+            creator_amount,
+            9,
+        )?;
+
+        // Treasury share from Escrow
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.escrow_account.to_account_info(),
+                    to: ctx.accounts.treasury_wsol_account.to_account_info(),
+                    mint: ctx.accounts.wsol_mint.to_account_info(),
+                    authority: ctx.accounts.moai.to_account_info(),
+                },
+            )
+            .with_signer(&[signer_seeds]),
+            treasury_amount,
+            9,
+        )?;
+
+        // Liquidity share stays in the escrow for seed_meme_pool
+        ctx.accounts.moai.unclaimed_prizes -= creator_amount + treasury_amount + liquidity_amount;
+        ctx.accounts.moai.liquidity_budget += liquidity_amount;
+        let epoch_result = &mut ctx.accounts.epoch_result;
+        epoch_result.voter_pool = voter_pool;
+        epoch_result.distributed = true;
+
+        let memo = format!(
+            "Distributed epoch {} : {} SOL to creator, {} SOL to treasury, {} SOL to liquidity, {} SOL to voters",
+            epoch_result.epoch,
+            amount_to_ui_amount_string(creator_amount, 9),
+            amount_to_ui_amount_string(treasury_amount, 9),
+            amount_to_ui_amount_string(liquidity_amount, 9),
+            amount_to_ui_amount_string(voter_pool, 9)
+        );
+
+        // MEMO
+        build_memo(
+            CpiContext::new(ctx.accounts.memo_program.to_account_info(), BuildMemo {}),
+            memo.as_bytes(),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn claim_prize(ctx: Context<ClaimPrize>) -> Result<()> {
+        let signer_seeds: &[&[u8]] = &[
+            b"moai".as_ref(),
+            ctx.accounts.moai.seed_authority.as_ref(),
+            &[ctx.accounts.moai.nonce],
+        ];
+
+        let epoch_result = &ctx.accounts.epoch_result;
+        let prize_amount = (epoch_result.voter_pool as u128 * ctx.accounts.owner_vote.count as u128)
+            .checked_div(epoch_result.winner_vote as u128)
+            .unwrap_or(0) as u64;
+
+        // Voter share from Escrow
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.escrow_account.to_account_info(),
+                    to: ctx.accounts.receiver_wsol_account.to_account_info(),
+                    mint: ctx.accounts.wsol_mint.to_account_info(),
+                    authority: ctx.accounts.moai.to_account_info(),
+                },
+            )
+            .with_signer(&[signer_seeds]),
+            prize_amount,
+            9,
+        )?;
+
+        ctx.accounts.moai.unclaimed_prizes -= prize_amount;
+        ctx.accounts.owner_vote.prize_claimed = true;
+
+        let memo = format!(
+            "Claimed {} SOL for {} votes on epoch {} winner",
+            amount_to_ui_amount_string(prize_amount, 9),
+            ctx.accounts.owner_vote.count,
+            ctx.accounts.epoch_result.epoch
+        );
+
+        // MEMO
+        build_memo(
+            CpiContext::new(ctx.accounts.memo_program.to_account_info(), BuildMemo {}),
+            memo.as_bytes(),
+        )?;
+
+        Ok(())
+    }
+
+    pub fn graduate_meme(ctx: Context<GraduateMeme>, symbol: String) -> Result<()> {
+        let signer_seeds: &[&[u8]] = &[
+            b"moai".as_ref(),
+            ctx.accounts.moai.seed_authority.as_ref(),
+            &[ctx.accounts.moai.nonce],
+        ];
+
+        if symbol.is_empty() || symbol.len() > MAX_SYMBOL_LEN {
+            return Err(MoaiError::InvalidSymbol.into());
+        }
+
+        // Metadata
+        create_metadata_accounts_v3(
+            CpiContext::new(
+                ctx.accounts.token_metadata_program.to_account_info(),
+                CreateMetadataAccountsV3 {
+                    metadata: ctx.accounts.metadata.to_account_info(),
+                    mint: ctx.accounts.meme_mint.to_account_info(),
+                    mint_authority: ctx.accounts.moai.to_account_info(),
+                    payer: ctx.accounts.authority.to_account_info(),
+                    update_authority: ctx.accounts.moai.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+            )
+            .with_signer(&[signer_seeds]),
+            DataV2 {
+                name: ctx.accounts.meme.name.clone(),
+                symbol: symbol.clone(),
+                uri: ctx.accounts.meme.uri.clone(),
+                seller_fee_basis_points: 0,
+                creators: None,
+                collection: None,
+                uses: None,
+            },
+            true,
+            true,
+            None,
+        )?;
+
+        // Mint the whole supply to Moai
+        mint_to(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                MintTo {
+                    mint: ctx.accounts.meme_mint.to_account_info(),
+                    to: ctx.accounts.moai_meme_account.to_account_info(),
+                    authority: ctx.accounts.moai.to_account_info(),
+                },
+            )
+            .with_signer(&[signer_seeds]),
+            MEME_TOKEN_SUPPLY,
+        )?;
+
+        // Fix the supply
+        set_authority(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                SetAuthority {
+                    current_authority: ctx.accounts.moai.to_account_info(),
+                    account_or_mint: ctx.accounts.meme_mint.to_account_info(),
+                },
+            )
+            .with_signer(&[signer_seeds]),
+            AuthorityType::MintTokens,
+            None,
+        )?;
+
+        let meme = &mut ctx.accounts.meme;
+        meme.graduated_mint = Some(ctx.accounts.meme_mint.key());
+
+        msg!(
+            "Meme {} graduated to ${} ({})",
+            meme.name,
+            symbol,
+            ctx.accounts.meme_mint.key()
+        );
+
+        Ok(())
+    }
+
+    pub fn init_meme_pool(
+        ctx: Context<InitMemePool>,
+        tick_spacing: u16,
+        initial_sqrt_price: u128,
+    ) -> Result<()> {
+        let token_mint_a = ctx.accounts.token_mint_a.key();
+        let token_mint_b = ctx.accounts.token_mint_b.key();
+        let meme_mint = ctx.accounts.meme.graduated_mint;
+        let wsol_is_token_a = if token_mint_a == native_mint::ID && Some(token_mint_b) == meme_mint
+        {
+            true
+        } else if token_mint_b == native_mint::ID && Some(token_mint_a) == meme_mint {
+            false
+        } else {
+            return Err(MoaiError::InvalidPoolMints.into());
+        };
+
+        whirlpool_cpi::cpi::initialize_pool(
+            CpiContext::new(
+                ctx.accounts.whirlpool_program.to_account_info(),
+                whirlpool_cpi::cpi::accounts::InitializePool {
+                    whirlpools_config: ctx.accounts.whirlpools_config.to_account_info(),
+                    token_mint_a: ctx.accounts.token_mint_a.to_account_info(),
+                    token_mint_b: ctx.accounts.token_mint_b.to_account_info(),
+                    funder: ctx.accounts.authority.to_account_info(),
+                    whirlpool: ctx.accounts.whirlpool.to_account_info(),
+                    token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+                    token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+                    fee_tier: ctx.accounts.fee_tier.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                },
+            ),
+            // passed bump is no longer used
+            WhirlpoolBumps { whirlpool_bump: 0 },
+            tick_spacing,
+            initial_sqrt_price,
+        )?;
+
+        // Tick arrays covering the full range position
+        let (tick_lower_index, tick_upper_index) = full_range_ticks(tick_spacing);
+        for (tick_array, tick) in [
+            (&ctx.accounts.tick_array_lower, tick_lower_index),
+            (&ctx.accounts.tick_array_upper, tick_upper_index),
+        ] {
+            whirlpool_cpi::cpi::initialize_tick_array(
+                CpiContext::new(
+                    ctx.accounts.whirlpool_program.to_account_info(),
+                    whirlpool_cpi::cpi::accounts::InitializeTickArray {
+                        whirlpool: ctx.accounts.whirlpool.to_account_info(),
+                        funder: ctx.accounts.authority.to_account_info(),
+                        tick_array: tick_array.to_account_info(),
+                        system_program: ctx.accounts.system_program.to_account_info(),
+                    },
+                ),
+                tick_array_start_index(tick, tick_spacing),
+            )?;
+        }
+
+        let meme_pool = &mut ctx.accounts.meme_pool;
+        meme_pool.meme = ctx.accounts.meme.key();
+        meme_pool.whirlpool = ctx.accounts.whirlpool.key();
+        meme_pool.wsol_is_token_a = wsol_is_token_a;
+        meme_pool.tick_array_lower = ctx.accounts.tick_array_lower.key();
+        meme_pool.tick_array_upper = ctx.accounts.tick_array_upper.key();
+        meme_pool.bump = ctx.bumps.meme_pool;
+
+        msg!(
+            "Whirlpool {} initialized for meme {}",
+            meme_pool.whirlpool,
+            ctx.accounts.meme.name
+        );
+
+        Ok(())
+    }
+
+    pub fn seed_meme_pool(
+        ctx: Context<SeedMemePool>,
+        liquidity: u128,
+        token_max_a: u64,
+        token_max_b: u64,
+    ) -> Result<()> {
+        let signer_seeds: &[&[u8]] = &[
+            b"moai".as_ref(),
+            ctx.accounts.moai.seed_authority.as_ref(),
+            &[ctx.accounts.moai.nonce],
+        ];
+
+        let wsol_is_token_a = ctx.accounts.meme_pool.wsol_is_token_a;
+        let (wsol_max, meme_max) = if wsol_is_token_a {
+            (token_max_a, token_max_b)
+        } else {
+            (token_max_b, token_max_a)
+        };
+        if liquidity == 0 || meme_max > MEME_POOL_ALLOCATION {
+            return Err(MoaiError::InvalidPoolDeposit.into());
+        }
+        // Only the liquidity share of past prizes may be deposited
+        if wsol_max > ctx.accounts.moai.liquidity_budget {
+            return Err(MoaiError::LiquidityBudgetExceeded.into());
+        }
+        let escrow_before = ctx.accounts.escrow_account.amount;
+
+        // Full range position owned by Moai
+        let (tick_lower_index, tick_upper_index) =
+            full_range_ticks(ctx.accounts.whirlpool.tick_spacing);
+        whirlpool_cpi::cpi::open_position(
+            CpiContext::new(
+                ctx.accounts.whirlpool_program.to_account_info(),
+                whirlpool_cpi::cpi::accounts::OpenPosition {
+                    funder: ctx.accounts.authority.to_account_info(),
+                    owner: ctx.accounts.moai.to_account_info(),
+                    position: ctx.accounts.position.to_account_info(),
+                    position_mint: ctx.accounts.position_mint.to_account_info(),
+                    position_token_account: ctx.accounts.position_token_account.to_account_info(),
+                    whirlpool: ctx.accounts.whirlpool.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    rent: ctx.accounts.rent.to_account_info(),
+                    associated_token_program: ctx
+                        .accounts
+                        .associated_token_program
+                        .to_account_info(),
+                },
+            ),
+            // passed bump is no longer used
+            OpenPositionBumps { position_bump: 0 },
+            tick_lower_index,
+            tick_upper_index,
+        )?;
+
+        // Deposit escrow WSOL and the meme allocation
+        let (token_owner_account_a, token_owner_account_b) = if wsol_is_token_a {
+            (
+                &ctx.accounts.escrow_account,
+                &ctx.accounts.moai_meme_account,
+            )
+        } else {
+            (
+                &ctx.accounts.moai_meme_account,
+                &ctx.accounts.escrow_account,
+            )
+        };
+        whirlpool_cpi::cpi::increase_liquidity(
+            CpiContext::new(
+                ctx.accounts.whirlpool_program.to_account_info(),
+                whirlpool_cpi::cpi::accounts::ModifyLiquidity {
+                    whirlpool: ctx.accounts.whirlpool.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    position_authority: ctx.accounts.moai.to_account_info(),
+                    position: ctx.accounts.position.to_account_info(),
+                    position_token_account: ctx.accounts.position_token_account.to_account_info(),
+                    token_owner_account_a: token_owner_account_a.to_account_info(),
+                    token_owner_account_b: token_owner_account_b.to_account_info(),
+                    token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+                    token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+                    tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+                    tick_array_upper: ctx.accounts.tick_array_upper.to_account_info(),
+                },
+            )
+            .with_signer(&[signer_seeds]),
+            liquidity,
+            token_max_a,
+            token_max_b,
+        )?;
+
+        ctx.accounts.escrow_account.reload()?;
+        let wsol_deposited = escrow_before - ctx.accounts.escrow_account.amount;
+        ctx.accounts.moai.liquidity_budget -= wsol_deposited;
+
+        let meme_pool = &mut ctx.accounts.meme_pool;
+        meme_pool.position = Some(ctx.accounts.position.key());
+        meme_pool.position_mint = ctx.accounts.position_mint.key();
+
+        msg!(
+            "Seeded whirlpool {} with {} liquidity",
+            meme_pool.whirlpool,
+            liquidity
+        );
+
+        Ok(())
+    }
+
+    pub fn harvest_lp_fees(ctx: Context<HarvestLpFees>) -> Result<()> {
+        let signer_seeds: &[&[u8]] = &[
+            b"moai".as_ref(),
+            ctx.accounts.moai.seed_authority.as_ref(),
+            &[ctx.accounts.moai.nonce],
+        ];
+
+        whirlpool_cpi::cpi::update_fees_and_rewards(CpiContext::new(
+            ctx.accounts.whirlpool_program.to_account_info(),
+            whirlpool_cpi::cpi::accounts::UpdateFeesAndRewards {
+                whirlpool: ctx.accounts.whirlpool.to_account_info(),
+                position: ctx.accounts.position.to_account_info(),
+                tick_array_lower: ctx.accounts.tick_array_lower.to_account_info(),
+                tick_array_upper: ctx.accounts.tick_array_upper.to_account_info(),
+            },
+        ))?;
+
+        // Collect into Moai, then split what arrived
+        let wsol_before = ctx.accounts.escrow_account.amount;
+        let meme_before = ctx.accounts.moai_meme_account.amount;
+        let (token_owner_account_a, token_owner_account_b) =
+            if ctx.accounts.meme_pool.wsol_is_token_a {
+                (
+                    &ctx.accounts.escrow_account,
+                    &ctx.accounts.moai_meme_account,
+                )
+            } else {
+                (
+                    &ctx.accounts.moai_meme_account,
+                    &ctx.accounts.escrow_account,
+                )
+            };
+        whirlpool_cpi::cpi::collect_fees(
+            CpiContext::new(
+                ctx.accounts.whirlpool_program.to_account_info(),
+                whirlpool_cpi::cpi::accounts::CollectFees {
+                    whirlpool: ctx.accounts.whirlpool.to_account_info(),
+                    position_authority: ctx.accounts.moai.to_account_info(),
+                    position: ctx.accounts.position.to_account_info(),
+                    position_token_account: ctx.accounts.position_token_account.to_account_info(),
+                    token_owner_account_a: token_owner_account_a.to_account_info(),
+                    token_vault_a: ctx.accounts.token_vault_a.to_account_info(),
+                    token_owner_account_b: token_owner_account_b.to_account_info(),
+                    token_vault_b: ctx.accounts.token_vault_b.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+            )
+            .with_signer(&[signer_seeds]),
+        )?;
+        ctx.accounts.escrow_account.reload()?;
+        ctx.accounts.moai_meme_account.reload()?;
+        let wsol_fees = ctx.accounts.escrow_account.amount - wsol_before;
+        let meme_fees = ctx.accounts.moai_meme_account.amount - meme_before;
+
+        let config = ctx.accounts.moai.config;
+        for (fees, from, mint, decimals, creator, treasury, buyback) in [
+            (
+                wsol_fees,
+                ctx.accounts.escrow_account.to_account_info(),
+                ctx.accounts.wsol_mint.to_account_info(),
+                9,
+                ctx.accounts.creator_wsol_account.to_account_info(),
+                ctx.accounts.treasury_wsol_account.to_account_info(),
+                ctx.accounts.buyback_wsol_account.to_account_info(),
+            ),
+            (
+                meme_fees,
+                ctx.accounts.moai_meme_account.to_account_info(),
+                ctx.accounts.meme_mint.to_account_info(),
+                MEME_TOKEN_DECIMALS,
+                ctx.accounts.creator_meme_account.to_account_info(),
+                ctx.accounts.treasury_meme_account.to_account_info(),
+                ctx.accounts.buyback_meme_account.to_account_info(),
+            ),
+        ] {
+            let creator_amount =
+                (fees as u128 * config.lp_creator_share_bps as u128 / BPS_DENOMINATOR) as u64;
+            let treasury_amount =
+                (fees as u128 * config.lp_treasury_share_bps as u128 / BPS_DENOMINATOR) as u64;
+            // Rounding dust goes to the buyback
+            let buyback_amount = fees - creator_amount - treasury_amount;
+            for (to, amount) in [
+                (creator, creator_amount),
+                (treasury, treasury_amount),
+                (buyback, buyback_amount),
+            ] {
+                transfer_from_moai(
+                    ctx.accounts.token_program.to_account_info(),
+                    TransferChecked {
+                        from: from.clone(),
+                        to,
+                        mint: mint.clone(),
+                        authority: ctx.accounts.moai.to_account_info(),
+                    },
+                    signer_seeds,
+                    amount,
+                    decimals,
+                )?;
+            }
+        }
+
+        msg!(
+            "Harvested {} WSOL and {} meme token fees from {}",
+            amount_to_ui_amount_string(wsol_fees, 9),
+            amount_to_ui_amount_string(meme_fees, MEME_TOKEN_DECIMALS),
+            ctx.accounts.whirlpool.key()
+        );
+
+        Ok(())
+    }
+
+    pub fn update_config(ctx: Context<UpdateConfig>, config: MoaiConfig) -> Result<()> {
+        config.validate()?;
+
+        let moai = &mut ctx.accounts.moai;
+        let old = moai.config;
+        // Outstanding $ROCK was bought at the old price and must sell back on the same curve
+        if config.rock_price != old.rock_price
+            && moai.curve_supply(ctx.accounts.rock_mint.supply) > 0
+        {
+            return Err(MoaiError::RockPriceLocked.into());
+        }
+        moai.config = config;
+
+        emit!(ConfigUpdated {
+            moai: moai.key(),
+            old,
+            new: config,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeMoai<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(init, payer=authority, space= 8+Moai::INIT_SPACE, seeds=[b"moai".as_ref(), authority.key().as_ref()], bump)]
+    pub moai: Account<'info, Moai>,
+    #[account(init, payer=authority, associated_token::mint=wsol_mint, associated_token::authority=moai)]
+    pub escrow_account: Account<'info, TokenAccount>,
+    #[account(address = native_mint::ID @ MoaiError::InvalidWsolMint)]
+    pub wsol_mint: Account<'info, Mint>,
+    #[account(init, payer=authority, mint::authority=moai, mint::decimals=9, mint::freeze_authority=moai)]
+    pub moai_mint: Account<'info, Mint>,
+    #[account(init, payer=authority, mint::authority=moai, mint::decimals=0, mint::freeze_authority=moai)]
+    pub rock_mint: Account<'info, Mint>,
+    #[account(init, payer=authority, space=8+std::mem::size_of::<Leaderboard>(), seeds=[b"leaderboard".as_ref(), moai.key().as_ref(), 0u64.to_le_bytes().as_ref()], bump)]
+    pub leaderboard: AccountLoader<'info, Leaderboard>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority @ MoaiError::Unauthorized, constraint = moai.authority_valid @ MoaiError::AuthorityRenounced, seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    pub new_authority: Signer<'info>,
+    #[account(mut, constraint = moai.authority_valid @ MoaiError::AuthorityRenounced, constraint = moai.pending_authority == Some(new_authority.key()) @ MoaiError::NotPendingAuthority, seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+}
+
+#[derive(Accounts)]
+pub struct RenounceAuthority<'info> {
+    pub authority: Signer<'info>,
+    // Nobody could lift a pause after renouncing
+    #[account(mut, has_one = authority @ MoaiError::Unauthorized, constraint = moai.authority_valid @ MoaiError::AuthorityRenounced, constraint = !moai.paused && moai.paused_ixs == 0 @ MoaiError::RenounceWhilePaused, seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+}
+
+#[derive(Accounts)]
+pub struct SetUriPrefixes<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority @ MoaiError::Unauthorized, constraint = moai.authority_valid @ MoaiError::AuthorityRenounced, seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+}
+
+#[derive(Accounts)]
+pub struct SetModerator<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority @ MoaiError::Unauthorized, constraint = moai.authority_valid @ MoaiError::AuthorityRenounced, seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+}
+
+#[derive(Accounts)]
+pub struct SetPause<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority @ MoaiError::Unauthorized, constraint = moai.authority_valid @ MoaiError::AuthorityRenounced, seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+}
+
+#[derive(Accounts)]
+pub struct CreateMeme<'info> {
+    #[account(mut)]
+    pub user_spending: Signer<'info>,
+    #[account(mut, seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+    #[account(init, payer = user_spending, space =8+Meme::INIT_SPACE , seeds=[b"meme".as_ref(), moai.key().as_ref(), moai.epoch.to_le_bytes().as_ref(), moai.meme_count.to_le_bytes().as_ref()], bump)]
+    pub meme: Account<'info, Meme>,
+    #[account(address = moai.rock_mint_account @ MoaiError::InvalidRockMint)]
+    pub rock_mint: Account<'info, Mint>,
+    #[account(mut, address = moai.moai_mint_account @ MoaiError::InvalidMoaiMint)]
+    pub moai_mint: Account<'info, Mint>,
+    #[account(mut, seeds=[b"leaderboard".as_ref(), moai.key().as_ref(), moai.epoch.to_le_bytes().as_ref()], bump)]
+    pub leaderboard: AccountLoader<'info, Leaderboard>,
+    #[account(seeds=[b"user".as_ref(), moai.key().as_ref(), user_spending.key().as_ref()], bump, constraint = user_info.owner == session.owner @ MoaiError::Unauthorized)]
+    pub user_info: Account<'info, User>,
+    #[account(mut, has_one = user_spending @ MoaiError::Unauthorized, seeds=[b"session".as_ref(), moai.key().as_ref(), session.owner.as_ref()], bump)]
+    pub session: Account<'info, Session>,
+    #[account(mut, address = user_info.rock_account @ MoaiError::InvalidRockAccount)]
+    pub user_rock_account: Account<'info, TokenAccount>,
+    #[account(mut, address = user_info.moai_account @ MoaiError::InvalidMoaiAccount)]
+    pub user_moai_account: Account<'info, TokenAccount>,
+    #[account(init_if_needed, payer=user_spending, seeds=[b"vote".as_ref(), user_info.owner.as_ref(), meme.key().as_ref()], bump, space=8+VoteStatus::INIT_SPACE)]
+    pub owner_vote: Account<'info, VoteStatus>,
+    #[account(init, payer=user_spending, associated_token::mint = rock_mint, associated_token::authority = meme)]
+    pub meme_rock_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub memo_program: Program<'info, Memo>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct MintRock<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(mut)]
+    pub user_spending: Signer<'info>,
+    #[account(init_if_needed, payer=user, seeds=[b"user".as_ref(), moai.key().as_ref(), user_spending.key().as_ref()], bump, space=8+User::INIT_SPACE)]
+    pub user_info: Account<'info, User>,
+    #[account(seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+    #[account(mut, address = moai.rock_mint_account @ MoaiError::InvalidRockMint)]
+    pub rock_mint: Account<'info, Mint>,
+    #[account(address = moai.moai_mint_account @ MoaiError::InvalidMoaiMint)]
+    pub moai_mint: Account<'info, Mint>,
+    #[account(init_if_needed, payer=user, associated_token::mint = rock_mint, associated_token::authority = user)]
+    pub user_rock_account: Account<'info, TokenAccount>,
+    #[account(init_if_needed, payer=user, associated_token::mint = moai_mint, associated_token::authority = user)]
+    pub user_moai_account: Account<'info, TokenAccount>,
+    #[account(mut, address = moai.escrow_account @ MoaiError::InvalidEscrowAccount)]
+    pub escrow_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub memo_program: Program<'info, Memo>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemRock<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+    #[account(mut, address = moai.rock_mint_account @ MoaiError::InvalidRockMint)]
+    pub rock_mint: Account<'info, Mint>,
+    #[account(address = native_mint::ID @ MoaiError::InvalidWsolMint)]
+    pub wsol_mint: Account<'info, Mint>,
+    #[account(mut, associated_token::mint = rock_mint, associated_token::authority = user)]
+    pub user_rock_account: Account<'info, TokenAccount>,
+    #[account(init_if_needed, payer=user, associated_token::mint = wsol_mint, associated_token::authority = user)]
+    pub user_wsol_account: Account<'info, TokenAccount>,
+    #[account(mut, address = moai.escrow_account @ MoaiError::InvalidEscrowAccount)]
+    pub escrow_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub memo_program: Program<'info, Memo>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct CreateSession<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: only recorded as the session key and approved as delegate
+    pub user_spending: UncheckedAccount<'info>,
+    #[account(seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+    #[account(init_if_needed, payer=user, seeds=[b"session".as_ref(), moai.key().as_ref(), user.key().as_ref()], bump, space=8+Session::INIT_SPACE)]
+    pub session: Account<'info, Session>,
+    #[account(address = moai.rock_mint_account @ MoaiError::InvalidRockMint)]
+    pub rock_mint: Account<'info, Mint>,
+    #[account(mut, associated_token::mint = rock_mint, associated_token::authority = user)]
+    pub user_rock_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeSession<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+    #[account(mut, close = user, seeds=[b"session".as_ref(), moai.key().as_ref(), user.key().as_ref()], bump)]
+    pub session: Account<'info, Session>,
+    #[account(mut, constraint = user_rock_account.mint == moai.rock_mint_account @ MoaiError::InvalidRockAccount, token::authority = user)]
+    pub user_rock_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSpendingSession<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(mut)]
+    pub user_spending: Signer<'info>,
+    #[account(seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+    #[account(mut, close = user, constraint = user_info.owner == user.key() @ MoaiError::Unauthorized, seeds=[b"user".as_ref(), moai.key().as_ref(), user_spending.key().as_ref()], bump)]
+    pub user_info: Account<'info, User>,
+    // Omitted when the owner's session belongs to another spending wallet
+    #[account(mut, close = user, has_one = user_spending @ MoaiError::Unauthorized, seeds=[b"session".as_ref(), moai.key().as_ref(), user.key().as_ref()], bump)]
+    pub session: Option<Account<'info, Session>>,
+    #[account(mut, address = user_info.rock_account @ MoaiError::InvalidRockAccount, token::authority = user)]
+    pub user_rock_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub memo_program: Program<'info, Memo>,
+}
+
+#[derive(Accounts)]
+pub struct Vote<'info> {
+    #[account(mut)]
+    pub user_spending: Signer<'info>,
+    #[account(mut, has_one = moai @ MoaiError::InvalidMeme, constraint = meme.epoch == moai.epoch @ MoaiError::EpochClosed, constraint = meme.status != MemeStatus::Banned @ MoaiError::MemeBanned)]
+    pub meme: Account<'info, Meme>,
+    #[account(mut, seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+    #[account(address = moai.rock_mint_account @ MoaiError::InvalidRockMint)]
+    pub rock_mint: Account<'info, Mint>,
+    #[account(mut, address = moai.moai_mint_account @ MoaiError::InvalidMoaiMint)]
+    pub moai_mint: Account<'info, Mint>,
+    #[account(mut, seeds=[b"leaderboard".as_ref(), moai.key().as_ref(), moai.epoch.to_le_bytes().as_ref()], bump)]
+    pub leaderboard: AccountLoader<'info, Leaderboard>,
+    #[account(seeds=[b"user".as_ref(), moai.key().as_ref(), user_spending.key().as_ref()], bump, constraint = user_info.owner == session.owner @ MoaiError::Unauthorized)]
+    pub user_info: Account<'info, User>,
+    #[account(mut, has_one = user_spending @ MoaiError::Unauthorized, seeds=[b"session".as_ref(), moai.key().as_ref(), session.owner.as_ref()], bump)]
+    pub session: Account<'info, Session>,
+    #[account(mut, address = user_info.rock_account @ MoaiError::InvalidRockAccount)]
+    pub user_rock_account: Account<'info, TokenAccount>,
+    #[account(mut, address = user_info.moai_account @ MoaiError::InvalidMoaiAccount)]
+    pub user_moai_account: Account<'info, TokenAccount>,
+    #[account(mut, associated_token::mint = rock_mint, associated_token::authority = meme)]
+    pub meme_rock_account: Account<'info, TokenAccount>,
+    #[account(init_if_needed, payer=user_spending, seeds=[b"vote".as_ref(), user_info.owner.as_ref(), meme.key().as_ref()], bump, space=8+VoteStatus::INIT_SPACE)]
+    pub owner_vote: Account<'info, VoteStatus>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub memo_program: Program<'info, Memo>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct Unvote<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: spending wallet the votes were cast with, tied to user through user_info
+    pub user_spending: UncheckedAccount<'info>,
+    #[account(mut, seeds=[b"meme".as_ref(), moai.key().as_ref(), moai.epoch.to_le_bytes().as_ref(), meme.id.to_le_bytes().as_ref()], bump = meme.bump, has_one = moai @ MoaiError::InvalidMeme, constraint = meme.epoch == moai.epoch @ MoaiError::EpochClosed, constraint = meme.status != MemeStatus::Banned @ MoaiError::MemeBanned)]
+    pub meme: Account<'info, Meme>,
+    #[account(mut, seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+    #[account(address = moai.rock_mint_account @ MoaiError::InvalidRockMint)]
+    pub rock_mint: Account<'info, Mint>,
+    #[account(mut, address = moai.moai_mint_account @ MoaiError::InvalidMoaiMint)]
+    pub moai_mint: Account<'info, Mint>,
+    #[account(mut, seeds=[b"leaderboard".as_ref(), moai.key().as_ref(), moai.epoch.to_le_bytes().as_ref()], bump)]
+    pub leaderboard: AccountLoader<'info, Leaderboard>,
+    #[account(seeds=[b"user".as_ref(), moai.key().as_ref(), user_spending.key().as_ref()], bump, constraint = user_info.owner == user.key() @ MoaiError::Unauthorized)]
+    pub user_info: Account<'info, User>,
+    #[account(mut, address = user_info.rock_account @ MoaiError::InvalidRockAccount)]
+    pub user_rock_account: Account<'info, TokenAccount>,
+    #[account(mut, address = user_info.moai_account @ MoaiError::InvalidMoaiAccount)]
+    pub user_moai_account: Account<'info, TokenAccount>,
+    #[account(mut, associated_token::mint = rock_mint, associated_token::authority = meme)]
+    pub meme_rock_account: Account<'info, TokenAccount>,
+    #[account(mut, seeds=[b"vote".as_ref(), user.key().as_ref(), meme.key().as_ref()], bump)]
+    pub owner_vote: Account<'info, VoteStatus>,
+    pub token_program: Program<'info, Token>,
+    pub memo_program: Program<'info, Memo>,
+}
+
+#[derive(Accounts)]
+pub struct ReportMeme<'info> {
+    #[account(mut)]
+    pub reporter: Signer<'info>,
+    #[account(seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+    #[account(mut, seeds=[b"meme".as_ref(), moai.key().as_ref(), meme.epoch.to_le_bytes().as_ref(), meme.id.to_le_bytes().as_ref()], bump = meme.bump, has_one = moai @ MoaiError::InvalidMeme, constraint = meme.status != MemeStatus::Banned @ MoaiError::MemeBanned)]
+    pub meme: Account<'info, Meme>,
+    #[account(init, payer=reporter, space=8+MemeReport::INIT_SPACE, seeds=[b"report".as_ref(), meme.key().as_ref(), reporter.key().as_ref()], bump)]
+    pub report: Account<'info, MemeReport>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ModerateMeme<'info> {
+    pub moderator: Signer<'info>,
+    #[account(mut, constraint = moai.is_moderator(moderator.key) @ MoaiError::Unauthorized, seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+    // Bans are final, banned memes are only left for refunds
+    #[account(mut, has_one = moai @ MoaiError::InvalidMeme, constraint = meme.status != MemeStatus::Banned @ MoaiError::MemeBanned)]
+    pub meme: Account<'info, Meme>,
+    #[account(mut, seeds=[b"leaderboard".as_ref(), moai.key().as_ref(), moai.epoch.to_le_bytes().as_ref()], bump)]
+    pub leaderboard: AccountLoader<'info, Leaderboard>,
+}
+
+#[derive(Accounts)]
+pub struct RerankMeme<'info> {
+    #[account(mut, seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+    #[account(has_one = moai @ MoaiError::InvalidMeme, constraint = meme.epoch == moai.epoch @ MoaiError::EpochClosed, constraint = meme.status != MemeStatus::Banned @ MoaiError::MemeBanned)]
+    pub meme: Account<'info, Meme>,
+    #[account(mut, seeds=[b"leaderboard".as_ref(), moai.key().as_ref(), moai.epoch.to_le_bytes().as_ref()], bump)]
+    pub leaderboard: AccountLoader<'info, Leaderboard>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimBanRefund<'info> {
+    pub user: Signer<'info>,
+    /// CHECK: spending wallet the votes were cast with, tied to user through user_info
+    pub user_spending: UncheckedAccount<'info>,
+    #[account(mut, seeds=[b"meme".as_ref(), moai.key().as_ref(), meme.epoch.to_le_bytes().as_ref(), meme.id.to_le_bytes().as_ref()], bump = meme.bump, has_one = moai @ MoaiError::InvalidMeme, constraint = meme.status == MemeStatus::Banned @ MoaiError::MemeNotBanned)]
+    pub meme: Account<'info, Meme>,
+    #[account(seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+    #[account(address = moai.rock_mint_account @ MoaiError::InvalidRockMint)]
+    pub rock_mint: Account<'info, Mint>,
+    #[account(seeds=[b"user".as_ref(), moai.key().as_ref(), user_spending.key().as_ref()], bump, constraint = user_info.owner == user.key() @ MoaiError::Unauthorized)]
+    pub user_info: Account<'info, User>,
+    #[account(mut, address = user_info.rock_account @ MoaiError::InvalidRockAccount)]
+    pub user_rock_account: Account<'info, TokenAccount>,
+    #[account(mut, associated_token::mint = rock_mint, associated_token::authority = meme)]
+    pub meme_rock_account: Account<'info, TokenAccount>,
+    // Votes that already took a share of the prize are not refunded
+    #[account(mut, seeds=[b"vote".as_ref(), user.key().as_ref(), meme.key().as_ref()], bump, constraint = !owner_vote.prize_claimed @ MoaiError::PrizeAlreadyClaimed)]
+    pub owner_vote: Account<'info, VoteStatus>,
+    pub token_program: Program<'info, Token>,
+    pub memo_program: Program<'info, Memo>,
+}
+
+#[derive(Accounts)]
+pub struct EndEpoch<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    // Anyone may end the epoch once the authority is renounced
+    #[account(mut, constraint = !moai.authority_valid || moai.authority == payer.key() @ MoaiError::Unauthorized, seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+    #[account(init, payer=payer, space=8+EpochResult::INIT_SPACE, seeds=[b"epoch".as_ref(), moai.key().as_ref(), moai.epoch.to_le_bytes().as_ref()], bump)]
+    pub epoch_result: Account<'info, EpochResult>,
+    #[account(seeds=[b"leaderboard".as_ref(), moai.key().as_ref(), moai.epoch.to_le_bytes().as_ref()], bump)]
+    pub leaderboard: AccountLoader<'info, Leaderboard>,
+    #[account(init, payer=payer, space=8+std::mem::size_of::<Leaderboard>(), seeds=[b"leaderboard".as_ref(), moai.key().as_ref(), (moai.epoch + 1).to_le_bytes().as_ref()], bump)]
+    pub next_leaderboard: AccountLoader<'info, Leaderboard>,
+    #[account(address = moai.rock_mint_account @ MoaiError::InvalidRockMint)]
+    pub rock_mint: Account<'info, Mint>,
+    #[account(address = moai.escrow_account @ MoaiError::InvalidEscrowAccount)]
+    pub escrow_account: Account<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DistributeEpoch<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut, seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+    #[account(mut, seeds=[b"epoch".as_ref(), moai.key().as_ref(), epoch_result.epoch.to_le_bytes().as_ref()], bump, constraint = !epoch_result.distributed @ MoaiError::PrizeAlreadyDistributed)]
+    pub epoch_result: Account<'info, EpochResult>,
+    #[account(constraint = epoch_result.winner == Some(winning_meme.key()) @ MoaiError::NotEpochWinner)]
+    pub winning_meme: Account<'info, Meme>,
+    /// CHECK: wallet of the winning meme creator
+    #[account(address = winning_meme.creator)]
+    pub creator: UncheckedAccount<'info>,
+    #[account(address = native_mint::ID @ MoaiError::InvalidWsolMint)]
+    pub wsol_mint: Account<'info, Mint>,
+    #[account(init_if_needed, payer=payer, associated_token::mint = wsol_mint, associated_token::authority = creator)]
+    pub creator_wsol_account: Account<'info, TokenAccount>,
+    #[account(mut, token::mint = wsol_mint, constraint = treasury_wsol_account.owner == moai.config.treasury @ MoaiError::InvalidTreasuryAccount)]
+    pub treasury_wsol_account: Account<'info, TokenAccount>,
+    #[account(mut, address = moai.escrow_account @ MoaiError::InvalidEscrowAccount)]
+    pub escrow_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub memo_program: Program<'info, Memo>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimPrize<'info> {
+    pub user: Signer<'info>,
+    #[account(mut, seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+    #[account(seeds=[b"epoch".as_ref(), moai.key().as_ref(), epoch_result.epoch.to_le_bytes().as_ref()], bump, constraint = epoch_result.distributed @ MoaiError::PrizeNotDistributed)]
+    pub epoch_result: Account<'info, EpochResult>,
+    #[account(constraint = epoch_result.winner == Some(meme.key()) @ MoaiError::NotEpochWinner)]
+    pub meme: Account<'info, Meme>,
+    #[account(mut, seeds=[b"vote".as_ref(), user.key().as_ref(), meme.key().as_ref()], bump, constraint = !owner_vote.prize_claimed @ MoaiError::PrizeAlreadyClaimed)]
+    pub owner_vote: Account<'info, VoteStatus>,
+    #[account(address = native_mint::ID @ MoaiError::InvalidWsolMint)]
+    pub wsol_mint: Account<'info, Mint>,
+    // Any WSOL account the owner wants the prize sent to
+    #[account(mut, token::mint = wsol_mint)]
+    pub receiver_wsol_account: Account<'info, TokenAccount>,
+    #[account(mut, address = moai.escrow_account @ MoaiError::InvalidEscrowAccount)]
+    pub escrow_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub memo_program: Program<'info, Memo>,
+}
+
+#[derive(Accounts)]
+pub struct GraduateMeme<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(has_one = authority @ MoaiError::Unauthorized, constraint = moai.authority_valid @ MoaiError::AuthorityRenounced, seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+    #[account(seeds=[b"epoch".as_ref(), moai.key().as_ref(), epoch_result.epoch.to_le_bytes().as_ref()], bump)]
+    pub epoch_result: Account<'info, EpochResult>,
+    #[account(mut, constraint = epoch_result.winner == Some(meme.key()) @ MoaiError::NotEpochWinner, constraint = meme.graduated_mint.is_none() @ MoaiError::MemeAlreadyGraduated, constraint = meme.status != MemeStatus::Banned @ MoaiError::MemeBanned)]
+    pub meme: Account<'info, Meme>,
+    #[account(init, payer=authority, seeds=[b"meme_mint".as_ref(), meme.key().as_ref()], bump, mint::decimals = MEME_TOKEN_DECIMALS, mint::authority = moai)]
+    pub meme_mint: Account<'info, Mint>,
+    /// CHECK: created by the token metadata program
+    #[account(mut, seeds=[b"metadata".as_ref(), token_metadata_program.key().as_ref(), meme_mint.key().as_ref()], bump, seeds::program = token_metadata_program.key())]
+    pub metadata: UncheckedAccount<'info>,
+    #[account(init, payer=authority, associated_token::mint = meme_mint, associated_token::authority = moai)]
+    pub moai_meme_account: Account<'info, TokenAccount>,
+    pub token_metadata_program: Program<'info, Metadata>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct InitMemePool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(has_one = authority @ MoaiError::Unauthorized, constraint = moai.authority_valid @ MoaiError::AuthorityRenounced, seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+    #[account(has_one = moai @ MoaiError::InvalidMeme, constraint = meme.graduated_mint.is_some() @ MoaiError::MemeNotGraduated)]
     pub meme: Account<'info, Meme>,
+    #[account(init, payer=authority, space=8+MemePool::INIT_SPACE, seeds=[b"meme_pool".as_ref(), meme.key().as_ref()], bump)]
+    pub meme_pool: Account<'info, MemePool>,
+    pub whirlpool_program: Program<'info, whirlpool_cpi::program::Whirlpool>,
+    pub whirlpools_config: Box<Account<'info, WhirlpoolsConfig>>,
+    pub token_mint_a: Account<'info, Mint>,
+    pub token_mint_b: Account<'info, Mint>,
+    /// CHECK: init by whirlpool
+    #[account(mut)]
+    pub whirlpool: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub token_vault_a: Signer<'info>,
+    #[account(mut)]
+    pub token_vault_b: Signer<'info>,
+    #[account(has_one = whirlpools_config)]
+    pub fee_tier: Account<'info, FeeTier>,
+    /// CHECK: init by whirlpool
     #[account(mut)]
+    pub tick_array_lower: UncheckedAccount<'info>,
+    /// CHECK: init by whirlpool
+    #[account(mut)]
+    pub tick_array_upper: UncheckedAccount<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct SeedMemePool<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority @ MoaiError::Unauthorized, constraint = moai.authority_valid @ MoaiError::AuthorityRenounced, seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
     pub moai: Account<'info, Moai>,
-    #[account(mut, mint::decimals = 0, mint::authority = moai)]
-    pub rock_mint: Account<'info, Mint>,
-    #[account(mut, mint::decimals = 9, mint::authority = moai)]
-    pub moai_mint: Account<'info, Mint>,
+    #[account(mut, has_one = whirlpool, has_one = tick_array_lower, has_one = tick_array_upper, constraint = meme_pool.position.is_none() @ MoaiError::MemePoolAlreadySeeded)]
+    pub meme_pool: Account<'info, MemePool>,
+    pub whirlpool_program: Program<'info, whirlpool_cpi::program::Whirlpool>,
     #[account(mut)]
-    pub user_rock_account: Account<'info, TokenAccount>,
+    pub whirlpool: Box<Account<'info, Whirlpool>>,
+    /// CHECK: init by whirlpool
     #[account(mut)]
-    pub user_moai_account: Account<'info, TokenAccount>,
-    #[account(mut, associated_token::mint = rock_mint, associated_token::authority = meme)]
-    pub meme_rock_account: Account<'info, TokenAccount>,
-    #[account(init_if_needed, payer=user_spending, seeds=[b"vote".as_ref(), user_spending.key().as_ref(), meme.key().as_ref()], bump, space=8+VoteStatus::INIT_SPACE)]
-    pub user_spending_vote: Account<'info, VoteStatus>,
+    pub position: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub position_mint: Signer<'info>,
+    /// CHECK: init by whirlpool
+    #[account(mut)]
+    pub position_token_account: UncheckedAccount<'info>,
+    #[account(mut, address = moai.escrow_account @ MoaiError::InvalidEscrowAccount)]
+    pub escrow_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, token::authority = moai, constraint = moai_meme_account.mint != native_mint::ID @ MoaiError::InvalidPoolMints, constraint = moai_meme_account.mint == whirlpool.token_mint_a || moai_meme_account.mint == whirlpool.token_mint_b @ MoaiError::InvalidPoolMints)]
+    pub moai_meme_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = whirlpool.token_vault_a)]
+    pub token_vault_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = whirlpool.token_vault_b)]
+    pub token_vault_b: Box<Account<'info, TokenAccount>>,
+    /// CHECK: initialized in init_meme_pool
+    #[account(mut)]
+    pub tick_array_lower: UncheckedAccount<'info>,
+    /// CHECK: initialized in init_meme_pool
+    #[account(mut)]
+    pub tick_array_upper: UncheckedAccount<'info>,
     pub token_program: Program<'info, Token>,
     pub associated_token_program: Program<'info, AssociatedToken>,
-    pub memo_program: Program<'info, Memo>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
 
+#[derive(Accounts)]
+pub struct HarvestLpFees<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Box<Account<'info, Moai>>,
+    #[account(constraint = meme.graduated_mint == Some(meme_mint.key()) @ MoaiError::InvalidPoolMints)]
+    pub meme: Box<Account<'info, Meme>>,
+    #[account(seeds=[b"meme_pool".as_ref(), meme.key().as_ref()], bump = meme_pool.bump, has_one = whirlpool, has_one = tick_array_lower, has_one = tick_array_upper, constraint = meme_pool.position == Some(position.key()) @ MoaiError::MemePoolNotSeeded)]
+    pub meme_pool: Box<Account<'info, MemePool>>,
+    pub whirlpool_program: Program<'info, whirlpool_cpi::program::Whirlpool>,
+    #[account(mut)]
+    pub whirlpool: Box<Account<'info, Whirlpool>>,
+    #[account(mut, has_one = whirlpool)]
+    pub position: Box<Account<'info, Position>>,
+    #[account(token::authority = moai, constraint = position_token_account.mint == position.position_mint, constraint = position_token_account.amount == 1)]
+    pub position_token_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: recorded on meme_pool, checked by whirlpool
+    pub tick_array_lower: UncheckedAccount<'info>,
+    /// CHECK: recorded on meme_pool, checked by whirlpool
+    pub tick_array_upper: UncheckedAccount<'info>,
+    #[account(mut, address = whirlpool.token_vault_a)]
+    pub token_vault_a: Box<Account<'info, TokenAccount>>,
+    #[account(mut, address = whirlpool.token_vault_b)]
+    pub token_vault_b: Box<Account<'info, TokenAccount>>,
+    #[account(address = native_mint::ID @ MoaiError::InvalidWsolMint)]
+    pub wsol_mint: Box<Account<'info, Mint>>,
+    pub meme_mint: Box<Account<'info, Mint>>,
+    #[account(mut, address = moai.escrow_account @ MoaiError::InvalidEscrowAccount)]
+    pub escrow_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, token::mint = meme_mint, token::authority = moai)]
+    pub moai_meme_account: Box<Account<'info, TokenAccount>>,
+    /// CHECK: wallet of the meme creator
+    #[account(address = meme.creator)]
+    pub creator: UncheckedAccount<'info>,
+    #[account(init_if_needed, payer=payer, associated_token::mint = wsol_mint, associated_token::authority = creator)]
+    pub creator_wsol_account: Box<Account<'info, TokenAccount>>,
+    // The creator never holds the meme token otherwise, so the crank opens it
+    #[account(init_if_needed, payer=payer, associated_token::mint = meme_mint, associated_token::authority = creator)]
+    pub creator_meme_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, token::mint = wsol_mint, constraint = treasury_wsol_account.owner == moai.config.treasury @ MoaiError::InvalidTreasuryAccount)]
+    pub treasury_wsol_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, token::mint = meme_mint, constraint = treasury_meme_account.owner == moai.config.treasury @ MoaiError::InvalidTreasuryAccount)]
+    pub treasury_meme_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, token::mint = wsol_mint, constraint = buyback_wsol_account.owner == moai.config.buyback @ MoaiError::InvalidBuybackAccount)]
+    pub buyback_wsol_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, token::mint = meme_mint, constraint = buyback_meme_account.owner == moai.config.buyback @ MoaiError::InvalidBuybackAccount)]
+    pub buyback_meme_account: Box<Account<'info, TokenAccount>>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub authority: Signer<'info>,
+    #[account(mut, has_one = authority @ MoaiError::Unauthorized, constraint = moai.authority_valid @ MoaiError::AuthorityRenounced, seeds=[b"moai".as_ref(), moai.seed_authority.as_ref()], bump = moai.nonce)]
+    pub moai: Account<'info, Moai>,
+    #[account(address = moai.rock_mint_account @ MoaiError::InvalidRockMint)]
+    pub rock_mint: Account<'info, Mint>,
+}
+
 #[account]
 #[derive(InitSpace, Default)]
 pub struct Moai {
     pub authority: Pubkey,
     pub current_top_vote: Option<Pubkey>,
+    pub current_top_vote_count: u64,
     pub epoch: u64,
     pub escrow_account: Pubkey,
     pub moai_mint_account: Pubkey,
     pub rock_mint_account: Pubkey,
     pub nonce: u8,
     pub authority_valid: bool,
+    // Authority the moai PDA was derived from, kept when the authority rotates
+    pub seed_authority: Pubkey,
+    pub pending_authority: Option<Pubkey>,
+    // Pauses every IX_* instruction
+    pub paused: bool,
+    // Pauses only the IX_* instructions set here
+    pub paused_ixs: u8,
+    pub epoch_start_ts: i64,
+    pub submission_end_ts: i64,
+    pub epoch_end_ts: i64,
+    pub config: MoaiConfig,
+    pub pricing_mode: PricingMode,
+    pub voting_mode: VotingMode,
+    // WSOL in the escrow owed to epoch winners and not yet paid out
+    pub unclaimed_prizes: u64,
+    // Memes created this epoch, also the id of the next meme
+    pub meme_count: u64,
+    // URI prefixes a meme's uri must start with
+    #[max_len(MAX_URI_PREFIXES, MAX_URI_PREFIX_LEN)]
+    pub uri_prefixes: Vec<String>,
+    // May hide and ban memes alongside the authority
+    pub moderator: Option<Pubkey>,
+    // $ROCK spent into past epochs' memes, no longer backed on the curve
+    pub rock_retired: u64,
+    // $ROCK spent into this epoch's memes, net of unvotes and ban refunds
+    pub epoch_rock_spent: u64,
+    // WSOL in the escrow set aside from prizes for seed_meme_pool
+    pub liquidity_budget: u64,
+}
+
+impl Moai {
+    pub fn sync_top_vote(&mut self, leaderboard: &Leaderboard) {
+        let top = match leaderboard.top() {
+            Some(top) => top,
+            None => {
+                self.current_top_vote = None;
+                self.current_top_vote_count = 0;
+                return;
+            }
+        };
+        if self.current_top_vote != Some(top.meme) {
+            msg!("Current top vote is {}", top.meme);
+        }
+        self.current_top_vote = Some(top.meme);
+        self.current_top_vote_count = top.votes;
+    }
+
+    pub fn is_moderator(&self, key: &Pubkey) -> bool {
+        (self.authority_valid && *key == self.authority) || self.moderator == Some(*key)
+    }
+
+    pub fn check_not_paused(&self, ix: u8) -> Result<()> {
+        if self.paused || self.paused_ixs & ix != 0 {
+            return Err(MoaiError::Paused.into());
+        }
+        Ok(())
+    }
+
+    // WSOL in the escrow that neither prizes nor redemptions may use
+    pub fn escrow_reserved(&self) -> u64 {
+        self.unclaimed_prizes + self.liquidity_budget
+    }
+
+    // $ROCK supply the pricing curve still backs with escrow SOL
+    pub fn curve_supply(&self, mint_supply: u64) -> u64 {
+        mint_supply - self.rock_retired
+    }
+
+    // Same durations as the current epoch, starting at `start_ts`
+    pub fn repeat_window(&self, start_ts: i64) -> EpochWindow {
+        EpochWindow {
+            start_ts,
+            submission_end_ts: start_ts + (self.submission_end_ts - self.epoch_start_ts),
+            end_ts: start_ts + (self.epoch_end_ts - self.epoch_start_ts),
+        }
+    }
+
+    pub fn set_window(&mut self, window: &EpochWindow) {
+        self.epoch_start_ts = window.start_ts;
+        self.submission_end_ts = window.submission_end_ts;
+        self.epoch_end_ts = window.end_ts;
+    }
+
+    pub fn check_submission_window(&self, now: i64) -> Result<()> {
+        if now < self.epoch_start_ts {
+            return Err(MoaiError::EpochNotStarted.into());
+        }
+        if now >= self.submission_end_ts {
+            return Err(MoaiError::SubmissionsClosed.into());
+        }
+        Ok(())
+    }
+
+    pub fn check_voting_window(&self, now: i64) -> Result<()> {
+        if now < self.epoch_start_ts {
+            return Err(MoaiError::EpochNotStarted.into());
+        }
+        if now >= self.epoch_end_ts {
+            return Err(MoaiError::VotingClosed.into());
+        }
+        Ok(())
+    }
+
+    // ROCK can be bought ahead of the round, but not once voting is over
+    pub fn check_sale_window(&self, now: i64) -> Result<()> {
+        if now >= self.epoch_end_ts {
+            return Err(MoaiError::SaleClosed.into());
+        }
+        Ok(())
+    }
+
+    pub fn check_redemption_window(&self, now: i64) -> Result<()> {
+        if now >= self.epoch_end_ts {
+            return Err(MoaiError::RedemptionClosed.into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Default)]
+pub struct MoaiConfig {
+    // SOL (lamports) paid into the escrow per $ROCK
+    pub rock_price: u64,
+    // SOL (lamports) per $ROCK kept in the spending wallet for tx fees
+    pub fee: u64,
+    // $MOAI (base units) minted per vote
+    pub moai_reward: u64,
+    // $ROCK spent to create a meme
+    pub meme_cost: u64,
+    // Share of a redemption kept in the escrow, in basis points
+    pub redemption_fee_bps: u16,
+    // Number of memes ranked on each epoch's leaderboard
+    pub leaderboard_size: u8,
+    // Shares of each epoch's prize pool, in basis points summing to 10000
+    pub creator_share_bps: u16,
+    pub voter_share_bps: u16,
+    pub treasury_share_bps: u16,
+    // Set aside for seeding graduated meme pools
+    pub liquidity_share_bps: u16,
+    // Owner of the WSOL account receiving the treasury share
+    pub treasury: Pubkey,
+    // Shares of harvested whirlpool fees, in basis points summing to 10000
+    pub lp_creator_share_bps: u16,
+    pub lp_treasury_share_bps: u16,
+    pub lp_buyback_share_bps: u16,
+    // Owner of the accounts funding $MOAI buybacks
+    pub buyback: Pubkey,
+}
+
+impl MoaiConfig {
+    pub fn validate(&self) -> Result<()> {
+        if self.rock_price == 0
+            || self.rock_price > MAX_ROCK_PRICE
+            || self.fee > MAX_FEE
+            || self.moai_reward > MAX_MOAI_REWARD
+            || self.meme_cost == 0
+            || self.meme_cost > MAX_MEME_COST
+            || self.redemption_fee_bps > MAX_REDEMPTION_FEE_BPS
+            || self.leaderboard_size < MIN_LEADERBOARD_SIZE
+            || self.leaderboard_size as usize > MAX_LEADERBOARD_SIZE
+            || self.creator_share_bps as u128
+                + self.voter_share_bps as u128
+                + self.treasury_share_bps as u128
+                + self.liquidity_share_bps as u128
+                != BPS_DENOMINATOR
+            || self.lp_creator_share_bps as u128
+                + self.lp_treasury_share_bps as u128
+                + self.lp_buyback_share_bps as u128
+                != BPS_DENOMINATOR
+        {
+            return Err(MoaiError::InvalidConfig.into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PricingMode {
+    // price = rock_price
+    #[default]
+    Flat,
+    // price = rock_price + slope * supply
+    Linear {
+        slope: u64,
+    },
+    // price = rock_price * (1 + growth_bps / 10000) ^ supply
+    Exponential {
+        growth_bps: u16,
+    },
+}
+
+impl PricingMode {
+    pub fn validate(&self) -> Result<()> {
+        if let PricingMode::Exponential { growth_bps } = *self {
+            if growth_bps == 0 || growth_bps > MAX_GROWTH_BPS {
+                return Err(MoaiError::InvalidConfig.into());
+            }
+        }
+        Ok(())
+    }
+
+    // SOL cost of minting `amount` ROCK on top of `supply`, rounded up
+    pub fn buy_cost(&self, base_price: u64, supply: u64, amount: u64) -> Result<u64> {
+        self.integral(base_price, supply, amount, true)
+    }
+
+    // SOL returned for burning `amount` ROCK down to `supply`, rounded down
+    pub fn sell_refund(&self, base_price: u64, supply: u64, amount: u64) -> Result<u64> {
+        self.integral(base_price, supply, amount, false)
+    }
+
+    // Sum of the price of every ROCK in [supply, supply + amount)
+    fn integral(&self, base_price: u64, supply: u64, amount: u64, round_up: bool) -> Result<u64> {
+        let base = base_price as u128;
+        let s = supply as u128;
+        let n = amount as u128;
+
+        let total = match *self {
+            PricingMode::Flat => n.checked_mul(base),
+            PricingMode::Linear { slope } => {
+                // n * base + slope * (n * s + n * (n - 1) / 2)
+                let steps = n
+                    .checked_mul(s)
+                    .and_then(|v| v.checked_add(n * n.saturating_sub(1) / 2));
+                steps
+                    .and_then(|v| v.checked_mul(slope as u128))
+                    .and_then(|v| v.checked_add(n.checked_mul(base)?))
+            }
+            PricingMode::Exponential { growth_bps } => {
+                // base * (g^(s + n) - g^s) / (g - 1)
+                let growth = CURVE_SCALE + CURVE_SCALE * growth_bps as u128 / BPS_DENOMINATOR;
+                let lower = pow_scaled(growth, s);
+                let upper = pow_scaled(growth, s + n);
+                match (lower, upper) {
+                    (Some(lower), Some(upper)) => base
+                        .checked_mul(upper - lower)
+                        .map(|v| div_round(v, growth - CURVE_SCALE, round_up)),
+                    _ => None,
+                }
+            }
+        };
+
+        total
+            .and_then(|v| u64::try_from(v).ok())
+            .ok_or_else(|| MoaiError::MathOverflow.into())
+    }
+}
+
+fn div_round(numerator: u128, denominator: u128, round_up: bool) -> u128 {
+    let quotient = numerator / denominator;
+    if round_up && quotient * denominator != numerator {
+        quotient + 1
+    } else {
+        quotient
+    }
+}
+
+// base^exp where base is scaled by CURVE_SCALE
+fn pow_scaled(base: u128, exp: u128) -> Option<u128> {
+    let mut result = CURVE_SCALE;
+    let mut base = base;
+    let mut exp = exp;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = result.checked_mul(base)? / CURVE_SCALE;
+        }
+        exp >>= 1;
+        if exp > 0 {
+            base = base.checked_mul(base)? / CURVE_SCALE;
+        }
+    }
+    Some(result)
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VotingMode {
+    // n votes cost n $ROCK
+    #[default]
+    Linear,
+    // A voter's n-th vote on a meme costs n^2 - (n - 1)^2 $ROCK
+    Quadratic,
+}
+
+impl VotingMode {
+    // $ROCK needed to add `votes` on top of a voter's `prior_votes` on a meme
+    pub fn rock_cost(&self, prior_votes: u64, votes: u64) -> Result<u64> {
+        let cost = match *self {
+            VotingMode::Linear => Some(votes),
+            VotingMode::Quadratic => prior_votes
+                .checked_add(votes)
+                .and_then(|total| total.checked_mul(total))
+                .map(|total_cost| total_cost - prior_votes * prior_votes),
+        };
+        cost.ok_or_else(|| MoaiError::MathOverflow.into())
+    }
+}
+
+#[event]
+pub struct ConfigUpdated {
+    pub moai: Pubkey,
+    pub old: MoaiConfig,
+    pub new: MoaiConfig,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct EpochWindow {
+    pub start_ts: i64,
+    pub submission_end_ts: i64,
+    pub end_ts: i64,
+}
+
+impl EpochWindow {
+    pub fn validate(&self) -> Result<()> {
+        if self.start_ts >= self.submission_end_ts || self.submission_end_ts > self.end_ts {
+            return Err(MoaiError::InvalidEpochWindow.into());
+        }
+        Ok(())
+    }
 }
 
 #[account]
 #[derive(InitSpace, Default)]
 pub struct Meme {
-    #[max_len(32)]
+    #[max_len(MAX_NAME_LEN)]
     pub name: String,
-    #[max_len(100)]
+    #[max_len(MAX_URI_LEN)]
     pub uri: String,
+    // sha256 of the content behind uri
+    pub content_hash: [u8; 32],
     pub creator: Pubkey,
     pub vote: u64,
+    pub moai: Pubkey,
+    pub epoch: u64,
+    // Sequential id within the epoch, part of the PDA seeds
+    pub id: u64,
+    pub bump: u8,
+    // Token minted for the meme once it wins an epoch
+    pub graduated_mint: Option<Pubkey>,
+    pub status: MemeStatus,
+    pub report_count: u64,
+    // $ROCK the creator paid, kept by the meme even if it is banned
+    pub creation_cost: u64,
+    // $ROCK spent on votes for the meme, net of unvotes
+    pub rock_voted: u64,
+    // Slot the meme reached its current vote count
+    pub last_vote_slot: u64,
+}
+
+#[derive(
+    AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Default, PartialEq, Eq, Debug,
+)]
+pub enum MemeStatus {
+    #[default]
+    Active,
+    // Still votable, left out of listings
+    Hidden,
+    // Off the leaderboard, voters can claim their $ROCK back
+    Banned,
+}
+
+#[account]
+#[derive(InitSpace, Default)]
+pub struct MemeReport {
+    pub meme: Pubkey,
+    pub reporter: Pubkey,
+    #[max_len(MAX_REPORT_REASON_LEN)]
+    pub reason: String,
+    pub slot: u64,
+}
+
+#[account]
+#[derive(InitSpace, Default)]
+pub struct MemePool {
+    pub meme: Pubkey,
+    pub whirlpool: Pubkey,
+    // Escrow WSOL is token A of the whirlpool, the meme token is token B
+    pub wsol_is_token_a: bool,
+    pub tick_array_lower: Pubkey,
+    pub tick_array_upper: Pubkey,
+    // Full range position owned by the moai, set once the pool is seeded.
+    // There is no instruction to decrease or close it, so the liquidity stays locked
+    pub position: Option<Pubkey>,
+    pub position_mint: Pubkey,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace, Default)]
+pub struct EpochResult {
+    pub epoch: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub voting_mode: VotingMode,
+    pub winner: Option<Pubkey>,
+    pub winner_vote: u64,
+    // Memes created in the epoch, with ids 0..meme_count
+    pub meme_count: u64,
+    // WSOL reserved for this epoch when it ended
+    pub prize_pool: u64,
+    // Part of the prize pool left for voters to claim
+    pub voter_pool: u64,
+    pub distributed: bool,
+    // Prize split copied from the config when the epoch ended
+    pub creator_share_bps: u16,
+    pub voter_share_bps: u16,
+    pub treasury_share_bps: u16,
+    pub liquidity_share_bps: u16,
+}
+
+#[account(zero_copy)]
+pub struct Leaderboard {
+    pub moai: Pubkey,
+    pub epoch: u64,
+    pub capacity: u8,
+    pub len: u8,
+    pub _padding: [u8; 2],
+    // Active memes of the epoch left off the board
+    pub unranked: u32,
+    // Sorted by votes, ties go to the meme that reached the count first
+    pub entries: [LeaderboardEntry; MAX_LEADERBOARD_SIZE],
+    // Best meme left off the board, kept at its current count
+    pub best_unranked: LeaderboardEntry,
+}
+
+#[zero_copy]
+#[derive(Default)]
+pub struct LeaderboardEntry {
+    pub meme: Pubkey,
+    pub votes: u64,
+    // Slot the meme reached its current vote count
+    pub last_vote_slot: u64,
+}
+
+impl LeaderboardEntry {
+    fn ranks_above(&self, other: &LeaderboardEntry) -> bool {
+        self.votes > other.votes
+            || (self.votes == other.votes && self.last_vote_slot <= other.last_vote_slot)
+    }
+
+    // Same order without the tie, only this may take another entry's place
+    fn strictly_ranks_above(&self, other: &LeaderboardEntry) -> bool {
+        self.votes > other.votes
+            || (self.votes == other.votes && self.last_vote_slot < other.last_vote_slot)
+    }
+}
+
+impl Leaderboard {
+    pub fn top(&self) -> Option<&LeaderboardEntry> {
+        self.entries[..self.len as usize].first()
+    }
+
+    // No meme left to win while some are still off the board
+    pub fn needs_rerank(&self) -> bool {
+        self.len == 0 && self.unranked > 0
+    }
+
+    pub fn add(&mut self, meme: Pubkey, votes: u64, slot: u64) {
+        self.unranked += 1;
+        self.record(meme, votes, slot);
+    }
+
+    pub fn remove(&mut self, meme: Pubkey) {
+        let len = self.len as usize;
+        if let Some(index) = self.entries[..len].iter().position(|e| e.meme == meme) {
+            self.entries.copy_within(index + 1..len, index);
+            self.len -= 1;
+            // The best unranked meme moves up into the freed slot
+            let best_unranked = self.best_unranked;
+            if best_unranked.meme != Pubkey::default() {
+                self.best_unranked = LeaderboardEntry::default();
+                self.record(
+                    best_unranked.meme,
+                    best_unranked.votes,
+                    best_unranked.last_vote_slot,
+                );
+            }
+        } else {
+            self.unranked = self.unranked.saturating_sub(1);
+            if self.best_unranked.meme == meme {
+                self.best_unranked = LeaderboardEntry::default();
+            }
+        }
+    }
+
+    fn beats_unranked(&self, entry: &LeaderboardEntry) -> bool {
+        self.best_unranked.meme == Pubkey::default() || entry.ranks_above(&self.best_unranked)
+    }
+
+    fn note_unranked(&mut self, entry: LeaderboardEntry) {
+        if self.beats_unranked(&entry) {
+            self.best_unranked = entry;
+        }
+    }
+
+    // A ranked meme may not be unvoted below a meme the board no longer tracks,
+    // otherwise the top entry could trail it
+    pub fn check_unvote(&self, meme: Pubkey, votes: u64, slot: u64) -> Result<()> {
+        let entry = LeaderboardEntry {
+            meme,
+            votes,
+            last_vote_slot: slot,
+        };
+        let ranked = self.entries[..self.len as usize]
+            .iter()
+            .any(|e| e.meme == meme);
+        if ranked && !self.beats_unranked(&entry) {
+            return Err(MoaiError::UnvoteBelowUnranked.into());
+        }
+        Ok(())
+    }
+
+    pub fn record(&mut self, meme: Pubkey, votes: u64, slot: u64) {
+        let entry = LeaderboardEntry {
+            meme,
+            votes,
+            last_vote_slot: slot,
+        };
+        let mut len = self.len as usize;
+        // The stored unranked meme's count changed, place it again from scratch
+        if self.best_unranked.meme == meme {
+            self.best_unranked = LeaderboardEntry::default();
+        }
+
+        if let Some(index) = self.entries[..len].iter().position(|e| e.meme == meme) {
+            self.entries.copy_within(index + 1..len, index);
+            len -= 1;
+        } else if !self.beats_unranked(&entry) {
+            // Free slots only take memes ahead of every unranked one
+            return;
+        } else if len == self.capacity as usize {
+            let last = self.entries[len - 1];
+            if !entry.strictly_ranks_above(&last) {
+                self.note_unranked(entry);
+                return;
+            }
+            // The last entry trades places with the newcomer
+            self.note_unranked(last);
+            len -= 1;
+        } else {
+            self.unranked = self.unranked.saturating_sub(1);
+        }
+
+        // Existing entries keep their place on a tie
+        let position = self.entries[..len]
+            .iter()
+            .position(|e| !e.ranks_above(&entry))
+            .unwrap_or(len);
+        self.entries.copy_within(position..len, position + 1);
+        self.entries[position] = entry;
+        self.len = (len + 1) as u8;
+    }
 }
 
 #[account]
 #[derive(InitSpace, Default)]
 pub struct VoteStatus {
     pub meme: Pubkey,
-    pub user_spending: Pubkey,
+    // Every spending wallet of the owner adds to the same count
+    pub owner: Pubkey,
     pub count: u64,
+    pub rock_spent: u64,
+    pub prize_claimed: bool,
+    // $MOAI minted for the votes in count
+    pub moai_minted: u64,
+}
+
+#[account]
+#[derive(InitSpace, Default)]
+pub struct Session {
+    pub owner: Pubkey,
+    pub user_spending: Pubkey,
+    pub expiry_slot: u64,
+    pub max_rock_spend: u64,
+    pub rock_spent: u64,
+    // IX_* flags the spending wallet may call
+    pub scope: u8,
+}
+
+impl Session {
+    pub fn spend(&mut self, ix: u8, slot: u64, rock_amount: u64) -> Result<()> {
+        if slot > self.expiry_slot {
+            return Err(MoaiError::SessionExpired.into());
+        }
+        if self.scope & ix == 0 {
+            return Err(MoaiError::SessionScopeNotAllowed.into());
+        }
+        let rock_spent = self
+            .rock_spent
+            .checked_add(rock_amount)
+            .ok_or(MoaiError::MathOverflow)?;
+        if rock_spent > self.max_rock_spend {
+            return Err(MoaiError::SessionSpendLimitExceeded.into());
+        }
+        self.rock_spent = rock_spent;
+        Ok(())
+    }
 }
 
 #[account]
 #[derive(InitSpace, Default)]
 pub struct User {
+    pub owner: Pubkey,
     pub rock_account: Pubkey,
     pub moai_account: Pubkey,
 }
@@ -451,6 +2747,376 @@ pub struct User {
 pub enum MoaiError {
     #[msg("Not enough $ROCK")]
     NotEnoughRock,
-    #[msg("Top Vote not provided")]
-    TopVoteNotProvided,
+    #[msg("Meme belongs to a closed epoch")]
+    EpochClosed,
+    #[msg("Epoch window must satisfy start < submission end <= end")]
+    InvalidEpochWindow,
+    #[msg("Epoch has not started yet")]
+    EpochNotStarted,
+    #[msg("Meme submissions are closed for this epoch")]
+    SubmissionsClosed,
+    #[msg("Voting is closed for this epoch")]
+    VotingClosed,
+    #[msg("$ROCK sale is closed for this epoch")]
+    SaleClosed,
+    #[msg("Epoch has not ended yet")]
+    EpochNotEnded,
+    #[msg("$MOAI account does not belong to the spending wallet's owner")]
+    InvalidMoaiAccount,
+    #[msg("Config value out of bounds")]
+    InvalidConfig,
+    #[msg("Math overflow")]
+    MathOverflow,
+    #[msg("Not enough SOL in escrow")]
+    InsufficientEscrow,
+    #[msg("$ROCK redemption is closed for this epoch")]
+    RedemptionClosed,
+    #[msg("Session expiry or scope is invalid")]
+    InvalidSession,
+    #[msg("Session has expired")]
+    SessionExpired,
+    #[msg("Session is not allowed to call this instruction")]
+    SessionScopeNotAllowed,
+    #[msg("Session $ROCK spend limit exceeded")]
+    SessionSpendLimitExceeded,
+    #[msg("Signer is not the moai authority")]
+    Unauthorized,
+    #[msg("Moai authority has been renounced")]
+    AuthorityRenounced,
+    #[msg("Signer is not the pending authority")]
+    NotPendingAuthority,
+    #[msg("Instruction is paused")]
+    Paused,
+    #[msg("$ROCK mint does not match moai")]
+    InvalidRockMint,
+    #[msg("$MOAI mint does not match moai")]
+    InvalidMoaiMint,
+    #[msg("WSOL mint must be the native mint")]
+    InvalidWsolMint,
+    #[msg("Escrow account does not match moai")]
+    InvalidEscrowAccount,
+    #[msg("$ROCK account does not belong to the spending wallet's owner")]
+    InvalidRockAccount,
+    #[msg("Vote amount must be greater than zero")]
+    InvalidVoteAmount,
+    #[msg("Spending wallet allowance is too low")]
+    InsufficientAllowance,
+    #[msg("Not enough $MOAI")]
+    NotEnoughMoai,
+    #[msg("Meme is not the epoch winner")]
+    NotEpochWinner,
+    #[msg("Epoch prize is already distributed")]
+    PrizeAlreadyDistributed,
+    #[msg("Epoch prize is not distributed yet")]
+    PrizeNotDistributed,
+    #[msg("Prize is already claimed")]
+    PrizeAlreadyClaimed,
+    #[msg("Treasury account is invalid")]
+    InvalidTreasuryAccount,
+    #[msg("Meme already graduated")]
+    MemeAlreadyGraduated,
+    #[msg("Token symbol is invalid")]
+    InvalidSymbol,
+    #[msg("Meme is not graduated")]
+    MemeNotGraduated,
+    #[msg("Whirlpool mints must be WSOL and the graduated meme token")]
+    InvalidPoolMints,
+    #[msg("Whirlpool deposit is invalid")]
+    InvalidPoolDeposit,
+    #[msg("Whirlpool is already seeded")]
+    MemePoolAlreadySeeded,
+    #[msg("Whirlpool is not seeded")]
+    MemePoolNotSeeded,
+    #[msg("Buyback account is invalid")]
+    InvalidBuybackAccount,
+    #[msg("Meme belongs to another moai")]
+    InvalidMeme,
+    #[msg("Meme name is empty")]
+    EmptyName,
+    #[msg("Meme name is too long")]
+    NameTooLong,
+    #[msg("Meme name contains control characters")]
+    InvalidName,
+    #[msg("Meme uri is too long")]
+    UriTooLong,
+    #[msg("Meme uri contains whitespace or control characters")]
+    InvalidUri,
+    #[msg("Meme uri does not start with an allowed prefix")]
+    UriNotAllowed,
+    #[msg("Allowed uri prefixes are invalid")]
+    InvalidUriPrefixes,
+    #[msg("Meme content hash is missing")]
+    InvalidContentHash,
+    #[msg("Meme is banned")]
+    MemeBanned,
+    #[msg("Meme is not banned")]
+    MemeNotBanned,
+    #[msg("Report reason is empty or too long")]
+    InvalidReportReason,
+    #[msg("Nothing to refund")]
+    NothingToRefund,
+    #[msg("Unpause every instruction before renouncing the authority")]
+    RenounceWhilePaused,
+    #[msg("Pause mask has bits outside the pausable instructions")]
+    InvalidPauseMask,
+    #[msg("Unvote would rank the meme below one left off the leaderboard")]
+    UnvoteBelowUnranked,
+    #[msg("Pool deposit exceeds the liquidity budget")]
+    LiquidityBudgetExceeded,
+    #[msg("Leaderboard is empty while memes are unranked, rerank one first")]
+    LeaderboardIncomplete,
+    #[msg("Rock price cannot change while $ROCK is outstanding")]
+    RockPriceLocked,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_prefixes() -> Vec<String> {
+        DEFAULT_URI_PREFIXES
+            .iter()
+            .map(|prefix| prefix.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn accepts_valid_meme() {
+        assert!(validate_meme_name("my crypto meme").is_ok());
+        assert!(validate_meme_name(&"a".repeat(MAX_NAME_LEN)).is_ok());
+        assert!(validate_meme_uri("https://gateway.irys.xyz/abc", &default_prefixes()).is_ok());
+        assert!(validate_meme_uri("ar://abc", &default_prefixes()).is_ok());
+    }
+
+    #[test]
+    fn rejects_empty_name() {
+        assert_eq!(validate_meme_name(""), Err(MoaiError::EmptyName.into()));
+        assert_eq!(validate_meme_name("   "), Err(MoaiError::EmptyName.into()));
+    }
+
+    #[test]
+    fn rejects_long_name() {
+        assert_eq!(
+            validate_meme_name(&"a".repeat(MAX_NAME_LEN + 1)),
+            Err(MoaiError::NameTooLong.into())
+        );
+        // Length is counted in bytes, as stored on chain
+        assert_eq!(
+            validate_meme_name(&"모".repeat(11)),
+            Err(MoaiError::NameTooLong.into())
+        );
+    }
+
+    #[test]
+    fn rejects_control_characters_in_name() {
+        assert_eq!(
+            validate_meme_name("meme\n"),
+            Err(MoaiError::InvalidName.into())
+        );
+        assert_eq!(
+            validate_meme_name("me\u{0}me"),
+            Err(MoaiError::InvalidName.into())
+        );
+    }
+
+    #[test]
+    fn rejects_long_uri() {
+        let uri = format!("ar://{}", "a".repeat(MAX_URI_LEN));
+        assert_eq!(
+            validate_meme_uri(&uri, &default_prefixes()),
+            Err(MoaiError::UriTooLong.into())
+        );
+    }
+
+    #[test]
+    fn rejects_whitespace_and_control_characters_in_uri() {
+        assert_eq!(
+            validate_meme_uri("ar://a b", &default_prefixes()),
+            Err(MoaiError::InvalidUri.into())
+        );
+        assert_eq!(
+            validate_meme_uri("ar://ab\t", &default_prefixes()),
+            Err(MoaiError::InvalidUri.into())
+        );
+    }
+
+    #[test]
+    fn rejects_uri_outside_allowlist() {
+        for uri in [
+            "",
+            "ar://",
+            "https://example.com/abc",
+            "ipfs://abc",
+            "AR://abc",
+        ] {
+            assert_eq!(
+                validate_meme_uri(uri, &default_prefixes()),
+                Err(MoaiError::UriNotAllowed.into())
+            );
+        }
+        assert_eq!(
+            validate_meme_uri("ar://abc", &[]),
+            Err(MoaiError::UriNotAllowed.into())
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_uri_prefixes() {
+        assert!(validate_uri_prefixes(&default_prefixes()).is_ok());
+        for prefixes in [
+            vec![],
+            vec![String::new()],
+            vec!["a".repeat(MAX_URI_PREFIX_LEN + 1)],
+            vec!["ar:// ".to_string()],
+            vec!["ar://".to_string(); MAX_URI_PREFIXES + 1],
+        ] {
+            assert_eq!(
+                validate_uri_prefixes(&prefixes),
+                Err(MoaiError::InvalidUriPrefixes.into())
+            );
+        }
+    }
+
+    fn leaderboard(capacity: u8) -> Leaderboard {
+        Leaderboard {
+            moai: Pubkey::default(),
+            epoch: 0,
+            capacity,
+            len: 0,
+            _padding: [0; 2],
+            unranked: 0,
+            entries: [LeaderboardEntry::default(); MAX_LEADERBOARD_SIZE],
+            best_unranked: LeaderboardEntry::default(),
+        }
+    }
+
+    #[test]
+    fn rejects_unvote_below_unranked_meme() {
+        let (a, b, c) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mut board = leaderboard(2);
+        board.add(a, 10, 1);
+        board.add(b, 5, 2);
+        board.add(c, 5, 3);
+        assert_eq!(board.len, 2);
+        assert_eq!(board.best_unranked.meme, c);
+
+        // a may fall behind b, but not behind the unranked c
+        assert!(board.check_unvote(a, 6, 4).is_ok());
+        assert_eq!(
+            board.check_unvote(a, 5, 4),
+            Err(MoaiError::UnvoteBelowUnranked.into())
+        );
+        // Unranked memes are not held to it
+        assert!(board.check_unvote(c, 4, 4).is_ok());
+    }
+
+    #[test]
+    fn lowers_best_unranked_when_it_is_unvoted() {
+        let (a, b, c) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mut board = leaderboard(2);
+        board.add(a, 10, 1);
+        board.add(b, 5, 2);
+        board.add(c, 5, 3);
+        assert_eq!(board.best_unranked.votes, 5);
+
+        board.record(c, 1, 4);
+        assert_eq!(board.best_unranked.meme, c);
+        assert_eq!(board.best_unranked.votes, 1);
+        assert!(board.check_unvote(b, 4, 5).is_ok());
+    }
+
+    #[test]
+    fn promotes_best_unranked_when_ranked_memes_are_banned() {
+        let (a, b, c) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mut board = leaderboard(2);
+        board.add(a, 10, 1);
+        board.add(b, 9, 2);
+        board.add(c, 8, 3);
+        assert_eq!(board.best_unranked.meme, c);
+
+        board.remove(a);
+        board.remove(b);
+        assert_eq!(board.len, 1);
+        assert_eq!(board.top().unwrap().meme, c);
+        assert_eq!(board.unranked, 0);
+        assert!(!board.needs_rerank());
+    }
+
+    #[test]
+    fn needs_rerank_once_bans_empty_the_board() {
+        let (a, b, c, d) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mut board = leaderboard(2);
+        board.add(a, 10, 1);
+        board.add(b, 9, 2);
+        board.add(c, 8, 3);
+        board.add(d, 7, 4);
+        assert_eq!(board.unranked, 2);
+
+        // Banning the stored unranked meme does not leave it blocking others
+        board.remove(c);
+        assert_eq!(board.best_unranked.meme, Pubkey::default());
+        board.remove(a);
+        board.remove(b);
+        assert!(board.needs_rerank());
+
+        board.record(d, 7, 4);
+        assert_eq!(board.top().unwrap().meme, d);
+        assert!(!board.needs_rerank());
+    }
+
+    #[test]
+    fn keeps_last_entry_on_a_full_board_tie() {
+        let (a, b, c) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mut board = leaderboard(2);
+        board.add(a, 5, 1);
+        board.add(b, 3, 2);
+
+        // Same votes in the same slot does not push b off
+        board.add(c, 3, 2);
+        assert_eq!(board.len, 2);
+        assert_eq!(board.entries[1].meme, b);
+        assert_eq!(board.best_unranked.meme, c);
+
+        board.record(c, 4, 3);
+        assert_eq!(board.entries[1].meme, c);
+    }
+
+    #[test]
+    fn rejects_single_slot_leaderboard() {
+        let config = MoaiConfig {
+            rock_price: LAMPORT,
+            meme_cost: 1,
+            leaderboard_size: 1,
+            creator_share_bps: 10000,
+            lp_creator_share_bps: 10000,
+            ..Default::default()
+        };
+        assert_eq!(config.validate(), Err(MoaiError::InvalidConfig.into()));
+        let config = MoaiConfig {
+            leaderboard_size: MIN_LEADERBOARD_SIZE,
+            ..config
+        };
+        assert!(config.validate().is_ok());
+    }
 }
@@ -0,0 +1,139 @@
+//! Off-chain check that the content behind a meme's uri still matches the
+//! `content_hash` stored on its `Meme` account.
+//!
+//! Fetch the account, decode it with `Meme::try_deserialize`, then call
+//! [`verify_meme`] with any [`ContentStore`].
+
+use std::{fmt, fs, io, path::PathBuf};
+
+use anchor_lang::solana_program::hash::hash;
+
+use crate::Meme;
+
+/// Source of the bytes behind a meme uri.
+pub trait ContentStore {
+    fn fetch(&self, uri: &str) -> io::Result<Vec<u8>>;
+}
+
+/// Serves content from a local directory, keyed by the last path segment of
+/// the uri, so `ar://abc` and `https://gateway.irys.xyz/abc` both read `abc`.
+pub struct LocalStore {
+    root: PathBuf,
+}
+
+impl LocalStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl ContentStore for LocalStore {
+    fn fetch(&self, uri: &str) -> io::Result<Vec<u8>> {
+        let key = uri
+            .rsplit('/')
+            .next()
+            .filter(|key| !key.is_empty() && *key != "." && *key != "..")
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("no content key in {}", uri),
+                )
+            })?;
+        fs::read(self.root.join(key))
+    }
+}
+
+#[derive(Debug)]
+pub enum VerifyError {
+    Fetch(io::Error),
+    HashMismatch {
+        expected: [u8; 32],
+        actual: [u8; 32],
+    },
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::Fetch(err) => write!(f, "failed to fetch meme content: {}", err),
+            VerifyError::HashMismatch { expected, actual } => write!(
+                f,
+                "meme content hash mismatch: expected {}, got {}",
+                to_hex(expected),
+                to_hex(actual)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for VerifyError {}
+
+/// sha256 of `content`, as passed to `create_meme`.
+pub fn content_hash(content: &[u8]) -> [u8; 32] {
+    hash(content).to_bytes()
+}
+
+pub fn verify_meme<S: ContentStore + ?Sized>(store: &S, meme: &Meme) -> Result<(), VerifyError> {
+    let content = store.fetch(&meme.uri).map_err(VerifyError::Fetch)?;
+    let actual = content_hash(&content);
+    if actual != meme.content_hash {
+        return Err(VerifyError::HashMismatch {
+            expected: meme.content_hash,
+            actual,
+        });
+    }
+    Ok(())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store_with(dir: &str, key: &str, content: &[u8]) -> LocalStore {
+        let root = std::env::temp_dir().join(dir);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join(key), content).unwrap();
+        LocalStore::new(root)
+    }
+
+    fn meme(uri: &str, content: &[u8]) -> Meme {
+        Meme {
+            uri: uri.to_string(),
+            content_hash: content_hash(content),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn verifies_matching_content() {
+        let store = store_with("moai-verifier-match", "abc", b"meme image");
+        assert!(verify_meme(&store, &meme("https://gateway.irys.xyz/abc", b"meme image")).is_ok());
+        assert!(verify_meme(&store, &meme("ar://abc", b"meme image")).is_ok());
+    }
+
+    #[test]
+    fn rejects_changed_content() {
+        let store = store_with("moai-verifier-mismatch", "abc", b"swapped image");
+        assert!(matches!(
+            verify_meme(&store, &meme("ar://abc", b"meme image")),
+            Err(VerifyError::HashMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn reports_missing_content() {
+        let store = LocalStore::new(std::env::temp_dir().join("moai-verifier-missing"));
+        assert!(matches!(
+            verify_meme(&store, &meme("ar://abc", b"meme image")),
+            Err(VerifyError::Fetch(_))
+        ));
+        assert!(matches!(
+            verify_meme(&store, &meme("ar://..", b"meme image")),
+            Err(VerifyError::Fetch(_))
+        ));
+    }
+}
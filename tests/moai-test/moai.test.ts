@@ -21,7 +21,13 @@ import {
     ASSOCIATED_TOKEN_PROGRAM_ID,
     getAssociatedTokenAddressSync,
     transferChecked,
+    approveChecked,
+    burn,
+    closeAccount,
     createAccount,
+    createMint,
+    getAccount,
+    getMint,
 } from '@solana/spl-token';
 import { assert } from 'chai';
 import BN from 'bn.js';
@@ -30,20 +36,36 @@ import {
     getMemeAddress,
     getVoteAddress,
     getUserInfoAddress,
+    getEpochResultAddress,
+    getSessionAddress,
+    getLeaderboardAddress,
+    getMemeMintAddress,
+    getMetadataAddress,
+    getMemePoolAddress,
+    getReportAddress,
+    TOKEN_METADATA_PROGRAM_ID,
 } from './util';
+import {
+    ORCA_WHIRLPOOL_PROGRAM_ID,
+    ORCA_WHIRLPOOLS_CONFIG,
+    PDAUtil,
+    PoolUtil,
+    PriceMath,
+    TickUtil,
+    WhirlpoolContext,
+    buildWhirlpoolClient,
+    IGNORE_CACHE,
+    increaseLiquidityQuoteByInputToken,
+    swapQuoteByInputToken,
+} from '@orca-so/whirlpools-sdk';
+import { DecimalUtil, Percentage } from '@orca-so/common-sdk';
+import Decimal from 'decimal.js';
 import Irys from '@irys/sdk';
 import path from 'path';
+import fs from 'fs';
 import { createHash } from 'crypto';
 import { sleep } from '@irys/sdk/build/cjs/common/utils';
 
-const hashValue = (name: string): Promise<string> =>
-    new Promise(resolve =>
-        setTimeout(
-            () => resolve(createHash('sha256').update(name).digest('hex')),
-            0,
-        ),
-    );
-
 // const TEST_PROVIDER_URL =
 //     'https://solana-devnet-archive.allthatnode.com/Ez7eqjgszCRYxMTozvryy4B5Y8qvR5Q7/';
 const TEST_PROVIDER_URL = 'http://localhost:8899';
@@ -74,6 +96,11 @@ const SOL = {
 
 const SPL_MEMO = new PublicKey('MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr');
 
+const IX_CREATE_MEME = 1 << 0;
+const IX_VOTE = 1 << 1;
+const IX_MINT_ROCK = 1 << 2;
+const IX_REDEEM_ROCK = 1 << 4;
+
 describe('moai-test', () => {
     const connection = new Connection(TEST_PROVIDER_URL);
     const testWallet = Keypair.fromSecretKey(
@@ -89,6 +116,7 @@ describe('moai-test', () => {
     const wallet = provider.wallet;
 
     const user = Keypair.generate();
+    const userSpending = Keypair.generate();
 
     const rockMint = Keypair.generate();
     const moaiMint = Keypair.generate();
@@ -96,18 +124,61 @@ describe('moai-test', () => {
 
     const escrowAccount = getAssociatedTokenAddressSync(SOL.mint, moai, true);
 
+    const getNextMemeAddress = async () => {
+        const moaiState = await program.account.moai.fetch(moai);
+        return getMemeAddress(
+            moai,
+            moaiState.epoch.toNumber(),
+            moaiState.memeCount.toNumber(),
+        );
+    };
+
+    const getContentHash = (filePath: string) =>
+        Array.from(
+            createHash('sha256').update(fs.readFileSync(filePath)).digest(),
+        );
+
     console.log('rockMint: ', rockMint.publicKey.toBase58());
     console.log('moaiMint: ', moaiMint.publicKey.toBase58());
     console.log('escrowAccount: ', escrowAccount.toBase58());
     console.log('moai: ', moai.toBase58());
 
+    const now = Math.floor(Date.now() / 1000);
+    const epochWindow = {
+        startTs: new BN(now - 10),
+        submissionEndTs: new BN(now + 60),
+        endTs: new BN(now + 90),
+    };
+    const moaiConfig = {
+        rockPrice: new BN(96_000_000),
+        fee: new BN(4_000_000),
+        moaiReward: new BN(LAMPORTS_PER_SOL),
+        memeCost: new BN(1),
+        redemptionFeeBps: 100,
+        leaderboardSize: 10,
+        creatorShareBps: 2000,
+        voterShareBps: 6000,
+        treasuryShareBps: 1000,
+        liquidityShareBps: 1000,
+        treasury: wallet.publicKey,
+        lpCreatorShareBps: 3000,
+        lpTreasuryShareBps: 2000,
+        lpBuybackShareBps: 5000,
+        buyback: wallet.publicKey,
+    };
+
     describe('initialize moai', () => {
         it('initialize moai', async () => {
             await connection.getLatestBlockhash().then(blockhash => {
                 console.log(blockhash);
             });
             const ix = await program.methods
-                .initializeMoai()
+                .initializeMoai(
+                    epochWindow,
+                    moaiConfig,
+                    { flat: {} },
+                    { linear: {} },
+                )
                 .accounts({
                     authority: wallet.publicKey,
                     moai,
@@ -115,6 +186,7 @@ describe('moai-test', () => {
                     wsolMint: SOL.mint,
                     moaiMint: moaiMint.publicKey,
                     rockMint: rockMint.publicKey,
+                    leaderboard: getLeaderboardAddress(moai, 0),
                     tokenProgram: TOKEN_PROGRAM_ID,
                     associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
                     systemProgram: SystemProgram.programId,
@@ -133,12 +205,136 @@ describe('moai-test', () => {
 
             console.log('initialize moai signature: ', signature);
         });
+
+        it('update config', async () => {
+            const signature = await program.methods
+                .updateConfig({ ...moaiConfig, fee: new BN(5_000_000) })
+                .accounts({
+                    authority: wallet.publicKey,
+                    moai,
+                    rockMint: rockMint.publicKey,
+                })
+                .rpc({ commitment: 'confirmed' });
+            console.log('update config signature: ', signature);
+
+            let moaiState = await program.account.moai.fetch(moai);
+            assert.equal(moaiState.config.fee.toNumber(), 5_000_000);
+
+            await program.methods
+                .updateConfig(moaiConfig)
+                .accounts({
+                    authority: wallet.publicKey,
+                    moai,
+                    rockMint: rockMint.publicKey,
+                })
+                .rpc({ commitment: 'confirmed' });
+            moaiState = await program.account.moai.fetch(moai);
+            assert.equal(moaiState.config.fee.toNumber(), 4_000_000);
+        });
+
+        it('transfer authority and back', async () => {
+            const newAuthority = Keypair.generate();
+
+            await program.methods
+                .proposeAuthority(newAuthority.publicKey)
+                .accounts({ authority: wallet.publicKey, moai })
+                .rpc({ commitment: 'confirmed' });
+
+            try {
+                await program.methods
+                    .acceptAuthority()
+                    .accounts({ newAuthority: user.publicKey, moai })
+                    .signers([user])
+                    .rpc({ commitment: 'confirmed' });
+                assert.fail('only the pending authority can accept');
+            } catch (e) {
+                assert.include(e.toString(), 'NotPendingAuthority');
+            }
+
+            await program.methods
+                .acceptAuthority()
+                .accounts({ newAuthority: newAuthority.publicKey, moai })
+                .signers([newAuthority])
+                .rpc({ commitment: 'confirmed' });
+            let moaiState = await program.account.moai.fetch(moai);
+            assert.isTrue(moaiState.authority.equals(newAuthority.publicKey));
+            assert.isTrue(moaiState.seedAuthority.equals(wallet.publicKey));
+
+            await program.methods
+                .proposeAuthority(wallet.publicKey)
+                .accounts({ authority: newAuthority.publicKey, moai })
+                .signers([newAuthority])
+                .rpc({ commitment: 'confirmed' });
+            await program.methods
+                .acceptAuthority()
+                .accounts({ newAuthority: wallet.publicKey, moai })
+                .rpc({ commitment: 'confirmed' });
+            moaiState = await program.account.moai.fetch(moai);
+            assert.isTrue(moaiState.authority.equals(wallet.publicKey));
+            assert.isNull(moaiState.pendingAuthority);
+        });
+
+        it('set uri prefixes', async () => {
+            const defaultPrefixes = (await program.account.moai.fetch(moai))
+                .uriPrefixes;
+            assert.include(defaultPrefixes, 'https://gateway.irys.xyz/');
+
+            await program.methods
+                .setUriPrefixes(['ar://'])
+                .accounts({ authority: wallet.publicKey, moai })
+                .rpc({ commitment: 'confirmed' });
+            let moaiState = await program.account.moai.fetch(moai);
+            assert.deepEqual(moaiState.uriPrefixes, ['ar://']);
+
+            try {
+                await program.methods
+                    .setUriPrefixes([])
+                    .accounts({ authority: wallet.publicKey, moai })
+                    .rpc({ commitment: 'confirmed' });
+                assert.fail('an empty allowlist should be rejected');
+            } catch (e) {
+                assert.include(e.toString(), 'InvalidUriPrefixes');
+            }
+
+            await program.methods
+                .setUriPrefixes(defaultPrefixes)
+                .accounts({ authority: wallet.publicKey, moai })
+                .rpc({ commitment: 'confirmed' });
+            moaiState = await program.account.moai.fetch(moai);
+            assert.deepEqual(moaiState.uriPrefixes, defaultPrefixes);
+        });
+
+        it('rejects out of bounds config', async () => {
+            try {
+                await program.methods
+                    .updateConfig({ ...moaiConfig, rockPrice: new BN(0) })
+                    .accounts({
+                        authority: wallet.publicKey,
+                        moai,
+                        rockMint: rockMint.publicKey,
+                    })
+                    .rpc({ commitment: 'confirmed' });
+                assert.fail('zero rock price should be rejected');
+            } catch (e) {
+                assert.include(e.toString(), 'InvalidConfig');
+            }
+        });
+
+        it('rejects an unknown pause bit', async () => {
+            try {
+                await program.methods
+                    .setPause(false, 1 << 7)
+                    .accounts({ authority: wallet.publicKey, moai })
+                    .rpc({ commitment: 'confirmed' });
+                assert.fail('an unknown pause bit should be rejected');
+            } catch (e) {
+                assert.include(e.toString(), 'InvalidPauseMask');
+            }
+        });
     });
 
     describe('user action', () => {
         const receiver = Keypair.generate();
-
-        const userSpending = Keypair.generate();
         const userRockAccount = getAssociatedTokenAddressSync(
             rockMint.publicKey,
             user.publicKey,
@@ -148,6 +344,8 @@ describe('moai-test', () => {
             user.publicKey,
         );
 
+        const session = getSessionAddress(moai, user.publicKey);
+
         let receiverRockAccount: PublicKey;
 
         before(async () => {
@@ -177,7 +375,7 @@ describe('moai-test', () => {
 
         it('deposit sol and mint rock', async () => {
             const signature = await program.methods
-                .mintRock(new BN('1'))
+                .mintRock(new BN('6'))
                 .accounts({
                     user: user.publicKey,
                     userSpending: userSpending.publicKey,
@@ -199,6 +397,181 @@ describe('moai-test', () => {
             console.log('mint rock signature: ', signature);
         });
 
+        it('rejects repricing outstanding rock', async () => {
+            try {
+                await program.methods
+                    .updateConfig({
+                        ...moaiConfig,
+                        rockPrice: moaiConfig.rockPrice.muln(2),
+                    })
+                    .accounts({
+                        authority: wallet.publicKey,
+                        moai,
+                        rockMint: rockMint.publicKey,
+                    })
+                    .rpc({ commitment: 'confirmed' });
+                assert.fail('the rock price should be locked');
+            } catch (e) {
+                assert.include(e.toString(), 'RockPriceLocked');
+            }
+        });
+
+        it('rejects mint rock while paused', async () => {
+            await program.methods
+                .setPause(false, IX_MINT_ROCK)
+                .accounts({ authority: wallet.publicKey, moai })
+                .rpc({ commitment: 'confirmed' });
+
+            try {
+                await program.methods
+                    .mintRock(new BN('1'))
+                    .accounts({
+                        user: user.publicKey,
+                        userSpending: userSpending.publicKey,
+                        moai,
+                        rockMint: rockMint.publicKey,
+                        moaiMint: moaiMint.publicKey,
+                        userRockAccount,
+                        userMoaiAccount,
+                        escrowAccount,
+                        userInfo: getUserInfoAddress(
+                            userSpending.publicKey,
+                            moai,
+                        ),
+                        tokenProgram: TOKEN_PROGRAM_ID,
+                        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                        systemProgram: SystemProgram.programId,
+                        memoProgram: SPL_MEMO,
+                        rent: SYSVAR_RENT_PUBKEY,
+                    })
+                    .signers([user, userSpending])
+                    .rpc({ commitment: 'confirmed' });
+                assert.fail('mint rock should be paused');
+            } catch (e) {
+                assert.include(e.toString(), 'Paused');
+            } finally {
+                await program.methods
+                    .setPause(false, 0)
+                    .accounts({ authority: wallet.publicKey, moai })
+                    .rpc({ commitment: 'confirmed' });
+            }
+        });
+
+        it('create session', async () => {
+            const expirySlot = (await connection.getSlot()) + 10_000;
+            const signature = await program.methods
+                .createSession(
+                    new BN(expirySlot),
+                    new BN('5'),
+                    IX_CREATE_MEME | IX_VOTE,
+                )
+                .accounts({
+                    user: user.publicKey,
+                    userSpending: userSpending.publicKey,
+                    moai,
+                    session,
+                    rockMint: rockMint.publicKey,
+                    userRockAccount,
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([user])
+                .rpc({ commitment: 'confirmed' });
+            console.log('create session signature: ', signature);
+
+            const sessionState = await program.account.session.fetch(session);
+            assert.isTrue(sessionState.owner.equals(user.publicKey));
+            assert.equal(sessionState.maxRockSpend.toNumber(), 5);
+            const rockAccount = await getAccount(connection, userRockAccount);
+            assert.isTrue(rockAccount.delegate.equals(userSpending.publicKey));
+        });
+
+        it('rejects redeem rock while paused', async () => {
+            await program.methods
+                .setPause(false, IX_REDEEM_ROCK)
+                .accounts({ authority: wallet.publicKey, moai })
+                .rpc({ commitment: 'confirmed' });
+
+            try {
+                await program.methods
+                    .redeemRock(new BN('1'))
+                    .accounts({
+                        user: user.publicKey,
+                        moai,
+                        rockMint: rockMint.publicKey,
+                        wsolMint: SOL.mint,
+                        userRockAccount,
+                        userWsolAccount: getAssociatedTokenAddressSync(
+                            SOL.mint,
+                            user.publicKey,
+                        ),
+                        escrowAccount,
+                        tokenProgram: TOKEN_PROGRAM_ID,
+                        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                        systemProgram: SystemProgram.programId,
+                        memoProgram: SPL_MEMO,
+                        rent: SYSVAR_RENT_PUBKEY,
+                    })
+                    .signers([user])
+                    .rpc({ commitment: 'confirmed' });
+                assert.fail('redeem rock should be paused');
+            } catch (e) {
+                assert.include(e.toString(), 'Paused');
+            } finally {
+                await program.methods
+                    .setPause(false, 0)
+                    .accounts({ authority: wallet.publicKey, moai })
+                    .rpc({ commitment: 'confirmed' });
+            }
+        });
+
+        it('redeem rock', async () => {
+            const userWsolAccount = getAssociatedTokenAddressSync(
+                SOL.mint,
+                user.publicKey,
+            );
+            const escrowBefore = await connection.getTokenAccountBalance(
+                escrowAccount,
+            );
+
+            const signature = await program.methods
+                .redeemRock(new BN('1'))
+                .accounts({
+                    user: user.publicKey,
+                    moai,
+                    rockMint: rockMint.publicKey,
+                    wsolMint: SOL.mint,
+                    userRockAccount,
+                    userWsolAccount,
+                    escrowAccount,
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                    memoProgram: SPL_MEMO,
+                    rent: SYSVAR_RENT_PUBKEY,
+                })
+                .signers([user])
+                .rpc({ commitment: 'confirmed' });
+            console.log('redeem rock signature: ', signature);
+
+            const escrowAfter = await connection.getTokenAccountBalance(
+                escrowAccount,
+            );
+            const refund = await connection.getTokenAccountBalance(
+                userWsolAccount,
+            );
+            const expectedRefund = moaiConfig.rockPrice
+                .muln(10000 - moaiConfig.redemptionFeeBps)
+                .divn(10000);
+            assert.equal(refund.value.amount, expectedRefund.toString());
+            assert.equal(
+                new BN(escrowBefore.value.amount)
+                    .sub(new BN(escrowAfter.value.amount))
+                    .toString(),
+                expectedRefund.toString(),
+            );
+        });
+
         // it('check approve', async () => {
         //     const signature = await transferChecked(
         //         provider.connection,
@@ -221,9 +594,7 @@ describe('moai-test', () => {
             const description =
                 'my crypto knowledge dumb but meme? I nailed it';
 
-            const index = await (await hashValue(name)).slice(0, 32);
-
-            const meme = getMemeAddress(index);
+            const meme = await getNextMemeAddress();
             console.log(meme.toBase58());
             const irys = await getIrys();
             // Your file
@@ -256,12 +627,12 @@ describe('moai-test', () => {
 
             const moai = getMoaiAddress(wallet.publicKey);
             console.log(
-                'user Spending Vote : ',
-                getVoteAddress(userSpending.publicKey, meme).toBase58(),
+                'owner vote : ',
+                getVoteAddress(user.publicKey, meme).toBase58(),
             );
 
             const signature = await program.methods
-                .createMeme(index, name, uri)
+                .createMeme(name, uri, getContentHash(filePath))
                 .accounts({
                     userSpending: userSpending.publicKey,
                     meme,
@@ -272,6 +643,9 @@ describe('moai-test', () => {
                         rockMint.publicKey,
                         user.publicKey,
                     ),
+                    leaderboard: getLeaderboardAddress(moai, 0),
+                    userInfo: getUserInfoAddress(userSpending.publicKey, moai),
+                    session,
                     userMoaiAccount: getAssociatedTokenAddressSync(
                         moaiMint.publicKey,
                         user.publicKey,
@@ -281,10 +655,7 @@ describe('moai-test', () => {
                         meme,
                         true,
                     ),
-                    userSpendingVote: getVoteAddress(
-                        userSpending.publicKey,
-                        meme,
-                    ),
+                    ownerVote: getVoteAddress(user.publicKey, meme),
                     tokenProgram: TOKEN_PROGRAM_ID,
                     associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
                     memoProgram: SPL_MEMO,
@@ -295,6 +666,17 @@ describe('moai-test', () => {
                 .rpc({ skipPreflight: true, commitment: 'finalized' });
 
             console.log('create meme signature: ', signature);
+
+            const moaiBalance = await connection.getTokenAccountBalance(
+                userMoaiAccount,
+                'finalized',
+            );
+            assert.equal(moaiBalance.value.uiAmount, 1);
+
+            const memeState = await program.account.meme.fetch(meme);
+            assert.isTrue(memeState.moai.equals(moai));
+            assert.equal(memeState.epoch.toNumber(), 0);
+            assert.deepEqual(memeState.contentHash, getContentHash(filePath));
         });
 
         it('create meme2', async () => {
@@ -304,9 +686,7 @@ describe('moai-test', () => {
             const description =
                 'my crypto knowledge dumb but meme? I nailed it';
 
-            const index = await (await hashValue(name)).slice(0, 32);
-
-            const meme = getMemeAddress(index);
+            const meme = await getNextMemeAddress();
             console.log(meme.toBase58());
             const irys = await getIrys();
             // Your file
@@ -339,25 +719,8 @@ describe('moai-test', () => {
 
             const moai = getMoaiAddress(wallet.publicKey);
 
-            let topVote: null | undefined | PublicKey = null;
-
-            while (topVote === null) {
-                topVote = await program.account.moai
-                    .fetch(moai)
-                    .then(moai => {
-                        console.log(moai);
-                        return moai.currentTopVote;
-                    })
-                    .catch(e => {
-                        console.log(e);
-                        return null;
-                    });
-                console.log('passed');
-                console.log('topVote : ', topVote && topVote.toBase58());
-            }
-
             const signature = await program.methods
-                .createMeme(index, name, uri)
+                .createMeme(name, uri, getContentHash(filePath))
                 .accounts({
                     userSpending: userSpending.publicKey,
                     meme,
@@ -368,6 +731,9 @@ describe('moai-test', () => {
                         rockMint.publicKey,
                         user.publicKey,
                     ),
+                    leaderboard: getLeaderboardAddress(moai, 0),
+                    userInfo: getUserInfoAddress(userSpending.publicKey, moai),
+                    session,
                     userMoaiAccount: getAssociatedTokenAddressSync(
                         moaiMint.publicKey,
                         user.publicKey,
@@ -377,31 +743,1759 @@ describe('moai-test', () => {
                         meme,
                         true,
                     ),
-                    userSpendingVote: getVoteAddress(
-                        userSpending.publicKey,
+                    ownerVote: getVoteAddress(user.publicKey, meme),
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                    memoProgram: SPL_MEMO,
+                    systemProgram: SystemProgram.programId,
+                    rent: SYSVAR_RENT_PUBKEY,
+                })
+                .signers([userSpending])
+                .rpc({ skipPreflight: true, commitment: 'confirmed' });
+
+            console.log('create meme signature: ', signature);
+
+            // Tied at 1 vote, the first meme keeps the lead
+            const firstMeme = getMemeAddress(moai, 0, 0);
+            const moaiState = await program.account.moai.fetch(moai);
+            assert.isTrue(moaiState.currentTopVote.equals(firstMeme));
+            assert.equal(moaiState.currentTopVoteCount.toNumber(), 1);
+
+            const leaderboard = await program.account.leaderboard.fetch(
+                getLeaderboardAddress(moai, moaiState.epoch.toNumber()),
+            );
+            assert.equal(leaderboard.len, 2);
+            assert.isTrue(leaderboard.entries[0].meme.equals(firstMeme));
+            assert.isTrue(leaderboard.entries[1].meme.equals(meme));
+        });
+
+        it('vote with multiple rock', async () => {
+            const meme = getMemeAddress(moai, 0, 1);
+            const ownerVote = getVoteAddress(user.publicKey, meme);
+
+            const signature = await program.methods
+                .vote(new BN('2'))
+                .accounts({
+                    userSpending: userSpending.publicKey,
+                    meme,
+                    moai,
+                    rockMint: rockMint.publicKey,
+                    moaiMint: moaiMint.publicKey,
+                    leaderboard: getLeaderboardAddress(moai, 0),
+                    userInfo: getUserInfoAddress(userSpending.publicKey, moai),
+                    session,
+                    userRockAccount,
+                    userMoaiAccount,
+                    memeRockAccount: getAssociatedTokenAddressSync(
+                        rockMint.publicKey,
                         meme,
+                        true,
                     ),
+                    ownerVote,
                     tokenProgram: TOKEN_PROGRAM_ID,
                     associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
                     memoProgram: SPL_MEMO,
                     systemProgram: SystemProgram.programId,
                     rent: SYSVAR_RENT_PUBKEY,
                 })
-                .remainingAccounts(
-                    topVote != undefined
-                        ? [
-                              {
-                                  pubkey: topVote,
-                                  isSigner: false,
-                                  isWritable: false,
-                              },
-                          ]
-                        : [],
-                )
                 .signers([userSpending])
-                .rpc({ skipPreflight: true });
+                .rpc({ commitment: 'confirmed' });
+            console.log('vote signature: ', signature);
 
-            console.log('create meme signature: ', signature);
+            const memeState = await program.account.meme.fetch(meme);
+            assert.equal(memeState.vote.toNumber(), 3);
+            const voteState = await program.account.voteStatus.fetch(
+                ownerVote,
+            );
+            assert.equal(voteState.count.toNumber(), 3);
+            // The creation cost is not counted as refundable spend
+            assert.equal(voteState.rockSpent.toNumber(), 2);
+            assert.equal(memeState.rockVoted.toNumber(), 2);
+            const moaiState = await program.account.moai.fetch(moai);
+            assert.isTrue(moaiState.currentTopVote.equals(meme));
+            const moaiBalance = await connection.getTokenAccountBalance(
+                userMoaiAccount,
+            );
+            assert.equal(moaiBalance.value.uiAmount, 4);
+        });
+
+        it('unvote', async () => {
+            const meme = getMemeAddress(moai, 0, 1);
+            const ownerVote = getVoteAddress(user.publicKey, meme);
+
+            const signature = await program.methods
+                .unvote(new BN('1'))
+                .accounts({
+                    user: user.publicKey,
+                    userSpending: userSpending.publicKey,
+                    meme,
+                    moai,
+                    rockMint: rockMint.publicKey,
+                    moaiMint: moaiMint.publicKey,
+                    leaderboard: getLeaderboardAddress(moai, 0),
+                    userInfo: getUserInfoAddress(userSpending.publicKey, moai),
+                    userRockAccount,
+                    userMoaiAccount,
+                    memeRockAccount: getAssociatedTokenAddressSync(
+                        rockMint.publicKey,
+                        meme,
+                        true,
+                    ),
+                    ownerVote,
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    memoProgram: SPL_MEMO,
+                })
+                .signers([user])
+                .rpc({ commitment: 'confirmed' });
+            console.log('unvote signature: ', signature);
+
+            const memeState = await program.account.meme.fetch(meme);
+            assert.equal(memeState.vote.toNumber(), 2);
+            const voteState = await program.account.voteStatus.fetch(
+                ownerVote,
+            );
+            assert.equal(voteState.count.toNumber(), 2);
+            assert.equal(voteState.rockSpent.toNumber(), 1);
+            assert.equal(
+                voteState.moaiMinted.toString(),
+                moaiConfig.moaiReward.muln(2).toString(),
+            );
+            assert.equal(memeState.rockVoted.toNumber(), 1);
+            const moaiState = await program.account.moai.fetch(moai);
+            assert.isTrue(moaiState.currentTopVote.equals(meme));
+            assert.equal(moaiState.currentTopVoteCount.toNumber(), 2);
+            const moaiBalance = await connection.getTokenAccountBalance(
+                userMoaiAccount,
+            );
+            assert.equal(moaiBalance.value.uiAmount, 3);
+        });
+
+        it('report meme', async () => {
+            const meme = getMemeAddress(moai, 0, 0);
+            const report = getReportAddress(meme, wallet.publicKey);
+
+            const signature = await program.methods
+                .reportMeme('spam')
+                .accounts({
+                    reporter: wallet.publicKey,
+                    moai,
+                    meme,
+                    report,
+                    systemProgram: SystemProgram.programId,
+                })
+                .rpc({ commitment: 'confirmed' });
+            console.log('report meme signature: ', signature);
+
+            const reportState = await program.account.memeReport.fetch(report);
+            assert.isTrue(reportState.reporter.equals(wallet.publicKey));
+            assert.equal(reportState.reason, 'spam');
+            const memeState = await program.account.meme.fetch(meme);
+            assert.equal(memeState.reportCount.toNumber(), 1);
+        });
+
+        it('moderate meme', async () => {
+            const moderator = Keypair.generate();
+            const meme = getMemeAddress(moai, 0, 0);
+            const moderateAccounts = {
+                moderator: moderator.publicKey,
+                moai,
+                meme,
+                leaderboard: getLeaderboardAddress(moai, 0),
+            };
+            const voteAccounts = {
+                userSpending: userSpending.publicKey,
+                meme,
+                moai,
+                rockMint: rockMint.publicKey,
+                moaiMint: moaiMint.publicKey,
+                leaderboard: getLeaderboardAddress(moai, 0),
+                userInfo: getUserInfoAddress(userSpending.publicKey, moai),
+                session,
+                userRockAccount,
+                userMoaiAccount,
+                memeRockAccount: getAssociatedTokenAddressSync(
+                    rockMint.publicKey,
+                    meme,
+                    true,
+                ),
+                ownerVote: getVoteAddress(user.publicKey, meme),
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                memoProgram: SPL_MEMO,
+                systemProgram: SystemProgram.programId,
+                rent: SYSVAR_RENT_PUBKEY,
+            };
+
+            // Back the meme so the ban leaves a refund to claim
+            await program.methods
+                .vote(new BN('1'))
+                .accounts(voteAccounts)
+                .signers([userSpending])
+                .rpc({ commitment: 'confirmed' });
+
+            await program.methods
+                .setModerator(moderator.publicKey)
+                .accounts({ authority: wallet.publicKey, moai })
+                .rpc({ commitment: 'confirmed' });
+
+            await program.methods
+                .moderateMeme({ hidden: {} })
+                .accounts(moderateAccounts)
+                .signers([moderator])
+                .rpc({ commitment: 'confirmed' });
+            let memeState = await program.account.meme.fetch(meme);
+            assert.deepEqual(memeState.status, { hidden: {} });
+
+            const signature = await program.methods
+                .moderateMeme({ banned: {} })
+                .accounts(moderateAccounts)
+                .signers([moderator])
+                .rpc({ commitment: 'confirmed' });
+            console.log('ban meme signature: ', signature);
+
+            memeState = await program.account.meme.fetch(meme);
+            assert.deepEqual(memeState.status, { banned: {} });
+            const leaderboard = await program.account.leaderboard.fetch(
+                getLeaderboardAddress(moai, 0),
+            );
+            assert.equal(leaderboard.len, 1);
+            assert.isFalse(leaderboard.entries[0].meme.equals(meme));
+
+            try {
+                await program.methods
+                    .vote(new BN('1'))
+                    .accounts(voteAccounts)
+                    .signers([userSpending])
+                    .rpc();
+                assert.fail('expected MemeBanned');
+            } catch (e) {
+                assert.include(e.toString(), 'MemeBanned');
+            }
+        });
+
+        it('rerank meme', async () => {
+            const rerankAccounts = (meme: PublicKey) => ({
+                moai,
+                meme,
+                leaderboard: getLeaderboardAddress(moai, 0),
+            });
+
+            // Anyone may rerank, a ranked meme keeps its place
+            const signature = await program.methods
+                .rerankMeme()
+                .accounts(rerankAccounts(getMemeAddress(moai, 0, 1)))
+                .rpc({ commitment: 'confirmed' });
+            console.log('rerank meme signature: ', signature);
+            const leaderboard = await program.account.leaderboard.fetch(
+                getLeaderboardAddress(moai, 0),
+            );
+            assert.equal(leaderboard.len, 1);
+
+            try {
+                await program.methods
+                    .rerankMeme()
+                    .accounts(rerankAccounts(getMemeAddress(moai, 0, 0)))
+                    .rpc();
+                assert.fail('expected MemeBanned');
+            } catch (e) {
+                assert.include(e.toString(), 'MemeBanned');
+            }
+        });
+
+        it('claim ban refund', async () => {
+            const meme = getMemeAddress(moai, 0, 0);
+            const ownerVote = getVoteAddress(user.publicKey, meme);
+            const rockBefore = await getAccount(connection, userRockAccount);
+
+            const signature = await program.methods
+                .claimBanRefund()
+                .accounts({
+                    user: user.publicKey,
+                    userSpending: userSpending.publicKey,
+                    meme,
+                    moai,
+                    rockMint: rockMint.publicKey,
+                    userInfo: getUserInfoAddress(userSpending.publicKey, moai),
+                    userRockAccount,
+                    memeRockAccount: getAssociatedTokenAddressSync(
+                        rockMint.publicKey,
+                        meme,
+                        true,
+                    ),
+                    ownerVote,
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    memoProgram: SPL_MEMO,
+                })
+                .signers([user])
+                .rpc({ commitment: 'confirmed' });
+            console.log('claim ban refund signature: ', signature);
+
+            // Only the vote comes back, the creation cost stays with the meme
+            const rockAfter = await getAccount(connection, userRockAccount);
+            assert.equal(rockAfter.amount - rockBefore.amount, BigInt(1));
+            const memeRockAccount = await getAccount(
+                connection,
+                getAssociatedTokenAddressSync(rockMint.publicKey, meme, true),
+            );
+            assert.equal(memeRockAccount.amount, BigInt(1));
+            const voteState = await program.account.voteStatus.fetch(
+                ownerVote,
+            );
+            assert.equal(voteState.count.toNumber(), 0);
+            assert.equal(voteState.rockSpent.toNumber(), 0);
+        });
+
+        it('revoke session', async () => {
+            const signature = await program.methods
+                .revokeSession()
+                .accounts({
+                    user: user.publicKey,
+                    moai,
+                    session,
+                    userRockAccount,
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                })
+                .signers([user])
+                .rpc({ commitment: 'confirmed' });
+            console.log('revoke session signature: ', signature);
+
+            assert.isNull(await connection.getAccountInfo(session));
+        });
+
+        it('close spending session', async () => {
+            const userInfo = getUserInfoAddress(userSpending.publicKey, moai);
+            await program.methods
+                .createSession(
+                    new BN((await connection.getSlot()) + 10_000),
+                    new BN('1'),
+                    IX_VOTE,
+                )
+                .accounts({
+                    user: user.publicKey,
+                    userSpending: userSpending.publicKey,
+                    moai,
+                    session,
+                    rockMint: rockMint.publicKey,
+                    userRockAccount,
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([user])
+                .rpc({ commitment: 'confirmed' });
+            const userBalanceBefore = await connection.getBalance(
+                user.publicKey,
+            );
+
+            const signature = await program.methods
+                .closeSpendingSession()
+                .accounts({
+                    user: user.publicKey,
+                    userSpending: userSpending.publicKey,
+                    moai,
+                    userInfo,
+                    session,
+                    userRockAccount,
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                    memoProgram: SPL_MEMO,
+                })
+                .signers([user, userSpending])
+                .rpc({ commitment: 'confirmed' });
+            console.log('close spending session signature: ', signature);
+
+            assert.isNull(await connection.getAccountInfo(userInfo));
+            assert.isNull(await connection.getAccountInfo(session));
+            assert.equal(
+                await connection.getBalance(userSpending.publicKey),
+                0,
+            );
+            assert.isAbove(
+                await connection.getBalance(user.publicKey),
+                userBalanceBefore,
+            );
+            const rockAccount = await getAccount(connection, userRockAccount);
+            assert.isNull(rockAccount.delegate);
+        });
+    });
+
+    describe('session limits', () => {
+        const owner = Keypair.generate();
+        const ownerSpending = Keypair.generate();
+        const ownerRockAccount = getAssociatedTokenAddressSync(
+            rockMint.publicKey,
+            owner.publicKey,
+        );
+        const ownerMoaiAccount = getAssociatedTokenAddressSync(
+            moaiMint.publicKey,
+            owner.publicKey,
+        );
+        const ownerSession = getSessionAddress(moai, owner.publicKey);
+
+        const createSession = async (
+            expirySlot: number,
+            maxRockSpend: number,
+            scope: number,
+        ) =>
+            program.methods
+                .createSession(new BN(expirySlot), new BN(maxRockSpend), scope)
+                .accounts({
+                    user: owner.publicKey,
+                    userSpending: ownerSpending.publicKey,
+                    moai,
+                    session: ownerSession,
+                    rockMint: rockMint.publicKey,
+                    userRockAccount: ownerRockAccount,
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([owner])
+                .rpc({ commitment: 'confirmed' });
+
+        const spendAccounts = (meme: PublicKey) => ({
+            userSpending: ownerSpending.publicKey,
+            meme,
+            moai,
+            rockMint: rockMint.publicKey,
+            moaiMint: moaiMint.publicKey,
+            leaderboard: getLeaderboardAddress(moai, 0),
+            userInfo: getUserInfoAddress(ownerSpending.publicKey, moai),
+            session: ownerSession,
+            userRockAccount: ownerRockAccount,
+            userMoaiAccount: ownerMoaiAccount,
+            memeRockAccount: getAssociatedTokenAddressSync(
+                rockMint.publicKey,
+                meme,
+                true,
+            ),
+            ownerVote: getVoteAddress(owner.publicKey, meme),
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            memoProgram: SPL_MEMO,
+            systemProgram: SystemProgram.programId,
+            rent: SYSVAR_RENT_PUBKEY,
+        });
+
+        const expectError = async (tx: Promise<string>, code: string) => {
+            try {
+                await tx;
+                assert.fail(`expected ${code}`);
+            } catch (e) {
+                assert.include(e.toString(), code);
+            }
+        };
+
+        const vote = (amount: number) =>
+            program.methods
+                .vote(new BN(amount))
+                .accounts(spendAccounts(getMemeAddress(moai, 0, 1)))
+                .signers([ownerSpending])
+                .rpc();
+
+        before(async () => {
+            await sendAndConfirmTransaction(
+                connection,
+                new Transaction().add(
+                    SystemProgram.transfer({
+                        fromPubkey: testWallet.publicKey,
+                        toPubkey: owner.publicKey,
+                        lamports: LAMPORTS_PER_SOL * 2,
+                    }),
+                ),
+                [testWallet],
+            );
+
+            await program.methods
+                .mintRock(new BN('3'))
+                .accounts({
+                    user: owner.publicKey,
+                    userSpending: ownerSpending.publicKey,
+                    moai,
+                    rockMint: rockMint.publicKey,
+                    moaiMint: moaiMint.publicKey,
+                    userRockAccount: ownerRockAccount,
+                    userMoaiAccount: ownerMoaiAccount,
+                    escrowAccount,
+                    userInfo: getUserInfoAddress(ownerSpending.publicKey, moai),
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                    memoProgram: SPL_MEMO,
+                    rent: SYSVAR_RENT_PUBKEY,
+                })
+                .signers([owner, ownerSpending])
+                .rpc({ commitment: 'confirmed' });
+        });
+
+        it('rejects an expired session', async () => {
+            const expirySlot = (await connection.getSlot()) + 3;
+            await createSession(expirySlot, 3, IX_VOTE);
+            while ((await connection.getSlot()) <= expirySlot) {
+                await sleep(400);
+            }
+
+            await expectError(vote(1), 'SessionExpired');
+        });
+
+        it('rejects an instruction outside the session scope', async () => {
+            await createSession(
+                (await connection.getSlot()) + 10_000,
+                3,
+                IX_VOTE,
+            );
+
+            const meme = await getNextMemeAddress();
+            await expectError(
+                program.methods
+                    .createMeme(
+                        'out of scope',
+                        'https://gateway.irys.xyz/out-of-scope',
+                        Array(32).fill(1),
+                    )
+                    .accounts(spendAccounts(meme))
+                    .signers([ownerSpending])
+                    .rpc(),
+                'SessionScopeNotAllowed',
+            );
+        });
+
+        it('rejects spending past the session limit', async () => {
+            await createSession(
+                (await connection.getSlot()) + 10_000,
+                1,
+                IX_VOTE,
+            );
+            // Raise the token allowance above the session limit so only the
+            // session itself stands in the way
+            await approveChecked(
+                connection,
+                testWallet,
+                ownerRockAccount,
+                rockMint.publicKey,
+                ownerSpending.publicKey,
+                owner,
+                3,
+                0,
+            );
+
+            await expectError(vote(2), 'SessionSpendLimitExceeded');
+        });
+    });
+
+    describe('account validation', () => {
+        const attacker = Keypair.generate();
+        const attackerSpending = Keypair.generate();
+        const attackerRockAccount = getAssociatedTokenAddressSync(
+            rockMint.publicKey,
+            attacker.publicKey,
+        );
+        const attackerMoaiAccount = getAssociatedTokenAddressSync(
+            moaiMint.publicKey,
+            attacker.publicKey,
+        );
+        const attackerSession = getSessionAddress(moai, attacker.publicKey);
+
+        const mintRockAccounts = (overrides: object = {}) => ({
+            user: attacker.publicKey,
+            userSpending: attackerSpending.publicKey,
+            moai,
+            rockMint: rockMint.publicKey,
+            moaiMint: moaiMint.publicKey,
+            userRockAccount: attackerRockAccount,
+            userMoaiAccount: attackerMoaiAccount,
+            escrowAccount,
+            userInfo: getUserInfoAddress(attackerSpending.publicKey, moai),
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            systemProgram: SystemProgram.programId,
+            memoProgram: SPL_MEMO,
+            rent: SYSVAR_RENT_PUBKEY,
+            ...overrides,
+        });
+
+        const expectError = async (tx: Promise<string>, code: string) => {
+            try {
+                await tx;
+                assert.fail(`expected ${code}`);
+            } catch (e) {
+                assert.include(e.toString(), code);
+            }
+        };
+
+        before(async () => {
+            await sendAndConfirmTransaction(
+                connection,
+                new Transaction().add(
+                    SystemProgram.transfer({
+                        fromPubkey: testWallet.publicKey,
+                        toPubkey: attacker.publicKey,
+                        lamports: LAMPORTS_PER_SOL * 2,
+                    }),
+                ),
+                [testWallet],
+            );
+
+            await program.methods
+                .mintRock(new BN('2'))
+                .accounts(mintRockAccounts())
+                .signers([attacker, attackerSpending])
+                .rpc({ commitment: 'confirmed' });
+
+            await program.methods
+                .createSession(
+                    new BN((await connection.getSlot()) + 10_000),
+                    new BN('2'),
+                    IX_CREATE_MEME | IX_VOTE,
+                )
+                .accounts({
+                    user: attacker.publicKey,
+                    userSpending: attackerSpending.publicKey,
+                    moai,
+                    session: attackerSession,
+                    rockMint: rockMint.publicKey,
+                    userRockAccount: attackerRockAccount,
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([attacker])
+                .rpc({ commitment: 'confirmed' });
+        });
+
+        it('rejects a substituted escrow account', async () => {
+            const fakeEscrow = await createAccount(
+                connection,
+                testWallet,
+                SOL.mint,
+                attacker.publicKey,
+            );
+            await expectError(
+                program.methods
+                    .mintRock(new BN('1'))
+                    .accounts(mintRockAccounts({ escrowAccount: fakeEscrow }))
+                    .signers([attacker, attackerSpending])
+                    .rpc(),
+                'InvalidEscrowAccount',
+            );
+        });
+
+        it('rejects a substituted rock mint', async () => {
+            const fakeRockMint = await createMint(
+                connection,
+                testWallet,
+                attacker.publicKey,
+                null,
+                0,
+            );
+            await expectError(
+                program.methods
+                    .mintRock(new BN('1'))
+                    .accounts(
+                        mintRockAccounts({
+                            rockMint: fakeRockMint,
+                            userRockAccount: getAssociatedTokenAddressSync(
+                                fakeRockMint,
+                                attacker.publicKey,
+                            ),
+                        }),
+                    )
+                    .signers([attacker, attackerSpending])
+                    .rpc(),
+                'InvalidRockMint',
+            );
+        });
+
+        const createMemeAccounts = (
+            meme: PublicKey,
+            overrides: object = {},
+        ) => ({
+            userSpending: attackerSpending.publicKey,
+            meme,
+            moai,
+            rockMint: rockMint.publicKey,
+            moaiMint: moaiMint.publicKey,
+            leaderboard: getLeaderboardAddress(moai, 0),
+            userInfo: getUserInfoAddress(attackerSpending.publicKey, moai),
+            session: attackerSession,
+            userRockAccount: attackerRockAccount,
+            userMoaiAccount: attackerMoaiAccount,
+            memeRockAccount: getAssociatedTokenAddressSync(
+                rockMint.publicKey,
+                meme,
+                true,
+            ),
+            ownerVote: getVoteAddress(attacker.publicKey, meme),
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            memoProgram: SPL_MEMO,
+            systemProgram: SystemProgram.programId,
+            rent: SYSVAR_RENT_PUBKEY,
+            ...overrides,
+        });
+
+        it("rejects spending another user's rock", async () => {
+            const meme = await getNextMemeAddress();
+            await expectError(
+                program.methods
+                    .createMeme(
+                        'stolen rock',
+                        'https://example.com',
+                        Array(32).fill(1),
+                    )
+                    .accounts(
+                        createMemeAccounts(meme, {
+                            userRockAccount: getAssociatedTokenAddressSync(
+                                rockMint.publicKey,
+                                user.publicKey,
+                            ),
+                        }),
+                    )
+                    .signers([attackerSpending])
+                    .rpc(),
+                'InvalidRockAccount',
+            );
+        });
+
+        it('rejects a substituted moai mint', async () => {
+            const fakeMoaiMint = await createMint(
+                connection,
+                testWallet,
+                attacker.publicKey,
+                null,
+                9,
+            );
+            const meme = await getNextMemeAddress();
+            await expectError(
+                program.methods
+                    .createMeme(
+                        'fake moai',
+                        'https://example.com',
+                        Array(32).fill(1),
+                    )
+                    .accounts(
+                        createMemeAccounts(meme, { moaiMint: fakeMoaiMint }),
+                    )
+                    .signers([attackerSpending])
+                    .rpc(),
+                'InvalidMoaiMint',
+            );
+        });
+
+        it("rejects another user's moai account", async () => {
+            const meme = await getNextMemeAddress();
+            await expectError(
+                program.methods
+                    .createMeme(
+                        'moai thief',
+                        'https://example.com',
+                        Array(32).fill(1),
+                    )
+                    .accounts(
+                        createMemeAccounts(meme, {
+                            userMoaiAccount: getAssociatedTokenAddressSync(
+                                moaiMint.publicKey,
+                                user.publicKey,
+                            ),
+                        }),
+                    )
+                    .signers([attackerSpending])
+                    .rpc(),
+                'InvalidMoaiAccount',
+            );
+        });
+
+        it('rejects actions outside the epoch windows', async () => {
+            const waitUntil = async (ts: number) => {
+                const waitMs = ts * 1000 - Date.now();
+                if (waitMs > 0) {
+                    await sleep(waitMs);
+                }
+            };
+            const moaiState = await program.account.moai.fetch(moai);
+
+            await waitUntil(moaiState.submissionEndTs.toNumber() + 2);
+            const meme = await getNextMemeAddress();
+            await expectError(
+                program.methods
+                    .createMeme(
+                        'too late',
+                        'https://gateway.irys.xyz/too-late',
+                        Array(32).fill(1),
+                    )
+                    .accounts(createMemeAccounts(meme))
+                    .signers([attackerSpending])
+                    .rpc(),
+                'SubmissionsClosed',
+            );
+
+            await waitUntil(moaiState.epochEndTs.toNumber() + 2);
+            await expectError(
+                program.methods
+                    .vote(new BN('1'))
+                    .accounts(createMemeAccounts(getMemeAddress(moai, 0, 1)))
+                    .signers([attackerSpending])
+                    .rpc(),
+                'VotingClosed',
+            );
+            await expectError(
+                program.methods
+                    .mintRock(new BN('1'))
+                    .accounts(mintRockAccounts())
+                    .signers([attacker, attackerSpending])
+                    .rpc(),
+                'SaleClosed',
+            );
+        });
+    });
+
+    describe('epoch lifecycle', () => {
+        it('end epoch', async () => {
+            const moaiState = await program.account.moai.fetch(moai);
+            const epoch = moaiState.epoch.toNumber();
+            const epochResult = getEpochResultAddress(moai, epoch);
+
+            const waitMs =
+                (moaiState.epochEndTs.toNumber() + 2) * 1000 - Date.now();
+            if (waitMs > 0) {
+                await sleep(waitMs);
+            }
+
+            const nextStart = moaiState.epochEndTs.toNumber();
+            const signature = await program.methods
+                .endEpoch(
+                    {
+                        startTs: new BN(nextStart),
+                        submissionEndTs: new BN(nextStart + 60),
+                        endTs: new BN(nextStart + 90),
+                    },
+                    { quadratic: {} },
+                )
+                .accounts({
+                    payer: wallet.publicKey,
+                    moai,
+                    epochResult,
+                    leaderboard: getLeaderboardAddress(moai, epoch),
+                    nextLeaderboard: getLeaderboardAddress(moai, epoch + 1),
+                    rockMint: rockMint.publicKey,
+                    escrowAccount,
+                    systemProgram: SystemProgram.programId,
+                })
+                .rpc({ commitment: 'confirmed' });
+            console.log('end epoch signature: ', signature);
+
+            const result = await program.account.epochResult.fetch(epochResult);
+            assert.equal(result.epoch.toNumber(), epoch);
+            assert.isTrue(result.winner.equals(moaiState.currentTopVote));
+            assert.equal(result.creatorShareBps, moaiConfig.creatorShareBps);
+            assert.equal(result.voterShareBps, moaiConfig.voterShareBps);
+            assert.equal(result.treasuryShareBps, moaiConfig.treasuryShareBps);
+            assert.equal(
+                result.liquidityShareBps,
+                moaiConfig.liquidityShareBps,
+            );
+
+            // Only the SOL behind spent $ROCK is paid out, held $ROCK stays
+            // redeemable on the flat curve
+            const nextMoaiState = await program.account.moai.fetch(moai);
+            const escrowBalance = await getAccount(connection, escrowAccount);
+            const supply = (await getMint(connection, rockMint.publicKey))
+                .supply;
+            const curveSupply = new BN(supply.toString()).sub(
+                nextMoaiState.rockRetired,
+            );
+            assert.isTrue(nextMoaiState.epochRockSpent.isZero());
+            assert.equal(
+                result.prizePool.toString(),
+                new BN(escrowBalance.amount.toString())
+                    .sub(curveSupply.mul(moaiConfig.rockPrice))
+                    .toString(),
+            );
+            assert.isTrue(result.prizePool.gtn(0));
+
+            assert.equal(nextMoaiState.epoch.toNumber(), epoch + 1);
+            assert.isNull(nextMoaiState.currentTopVote);
+            assert.equal(nextMoaiState.epochStartTs.toNumber(), nextStart);
+            assert.deepEqual(nextMoaiState.votingMode, { quadratic: {} });
+        });
+
+        it('distribute epoch', async () => {
+            const epochResult = getEpochResultAddress(moai, 0);
+            const result = await program.account.epochResult.fetch(epochResult);
+            const winningMeme = result.winner;
+            const creatorWsolAccount = getAssociatedTokenAddressSync(
+                SOL.mint,
+                userSpending.publicKey,
+            );
+            const treasuryWsolAccount = await createAccount(
+                connection,
+                testWallet,
+                SOL.mint,
+                wallet.publicKey,
+                Keypair.generate(),
+            );
+
+            const signature = await program.methods
+                .distributeEpoch()
+                .accounts({
+                    payer: wallet.publicKey,
+                    moai,
+                    epochResult,
+                    winningMeme,
+                    creator: userSpending.publicKey,
+                    wsolMint: SOL.mint,
+                    creatorWsolAccount,
+                    treasuryWsolAccount,
+                    escrowAccount,
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                    memoProgram: SPL_MEMO,
+                })
+                .rpc({ commitment: 'confirmed' });
+            console.log('distribute epoch signature: ', signature);
+
+            const prizePool = result.prizePool;
+            const creatorAmount = prizePool
+                .muln(result.creatorShareBps)
+                .divn(10000);
+            const treasuryAmount = prizePool
+                .muln(result.treasuryShareBps)
+                .divn(10000);
+            const liquidityAmount = prizePool
+                .muln(result.liquidityShareBps)
+                .divn(10000);
+            const distributed = await program.account.epochResult.fetch(
+                epochResult,
+            );
+            assert.isTrue(distributed.distributed);
+            assert.equal(
+                distributed.voterPool.toString(),
+                prizePool
+                    .sub(creatorAmount)
+                    .sub(treasuryAmount)
+                    .sub(liquidityAmount)
+                    .toString(),
+            );
+            const moaiState = await program.account.moai.fetch(moai);
+            assert.equal(
+                moaiState.liquidityBudget.toString(),
+                liquidityAmount.toString(),
+            );
+            const creatorBalance = await getAccount(
+                connection,
+                creatorWsolAccount,
+            );
+            assert.equal(
+                creatorBalance.amount.toString(),
+                creatorAmount.toString(),
+            );
+            const treasuryBalance = await getAccount(
+                connection,
+                treasuryWsolAccount,
+            );
+            assert.equal(
+                treasuryBalance.amount.toString(),
+                treasuryAmount.toString(),
+            );
+        });
+
+        it('claim prize', async () => {
+            const epochResult = getEpochResultAddress(moai, 0);
+            const result = await program.account.epochResult.fetch(epochResult);
+            const meme = result.winner;
+            const ownerVote = getVoteAddress(user.publicKey, meme);
+            const receiverWsolAccount = getAssociatedTokenAddressSync(
+                SOL.mint,
+                userSpending.publicKey,
+            );
+            const balanceBefore = await getAccount(
+                connection,
+                receiverWsolAccount,
+            );
+
+            const accounts = {
+                user: user.publicKey,
+                moai,
+                epochResult,
+                meme,
+                ownerVote,
+                wsolMint: SOL.mint,
+                receiverWsolAccount,
+                escrowAccount,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                memoProgram: SPL_MEMO,
+            };
+            const signature = await program.methods
+                .claimPrize()
+                .accounts(accounts)
+                .signers([user])
+                .rpc({ commitment: 'confirmed' });
+            console.log('claim prize signature: ', signature);
+
+            // The only voter on the winning meme takes the whole voter pool
+            const balanceAfter = await getAccount(
+                connection,
+                receiverWsolAccount,
+            );
+            assert.equal(
+                (balanceAfter.amount - balanceBefore.amount).toString(),
+                result.voterPool.toString(),
+            );
+            const moaiState = await program.account.moai.fetch(moai);
+            assert.equal(moaiState.unclaimedPrizes.toNumber(), 0);
+
+            try {
+                await program.methods
+                    .claimPrize()
+                    .accounts(accounts)
+                    .signers([user])
+                    .rpc({ commitment: 'confirmed' });
+                assert.fail('a prize can only be claimed once');
+            } catch (e) {
+                assert.include(e.toString(), 'PrizeAlreadyClaimed');
+            }
+        });
+
+        it('graduate meme', async () => {
+            const epochResult = getEpochResultAddress(moai, 0);
+            const result = await program.account.epochResult.fetch(epochResult);
+            const meme = result.winner;
+            const memeMint = getMemeMintAddress(meme);
+
+            const signature = await program.methods
+                .graduateMeme('MEME')
+                .accounts({
+                    authority: wallet.publicKey,
+                    moai,
+                    epochResult,
+                    meme,
+                    memeMint,
+                    metadata: getMetadataAddress(memeMint),
+                    moaiMemeAccount: getAssociatedTokenAddressSync(
+                        memeMint,
+                        moai,
+                        true,
+                    ),
+                    tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                    rent: SYSVAR_RENT_PUBKEY,
+                })
+                .rpc({ commitment: 'confirmed' });
+            console.log('graduate meme signature: ', signature);
+
+            const memeState = await program.account.meme.fetch(meme);
+            assert.isTrue(memeState.graduatedMint.equals(memeMint));
+            assert.isNotNull(
+                await connection.getAccountInfo(getMetadataAddress(memeMint)),
+            );
+            const mintState = await program.provider.connection
+                .getParsedAccountInfo(memeMint)
+                .then(info => (info.value.data as any).parsed.info);
+            assert.isNull(mintState.mintAuthority);
+        });
+
+        it('rejects banning a meme from a past epoch', async () => {
+            const result = await program.account.epochResult.fetch(
+                getEpochResultAddress(moai, 0),
+            );
+            const moaiState = await program.account.moai.fetch(moai);
+            try {
+                await program.methods
+                    .moderateMeme({ banned: {} })
+                    .accounts({
+                        moderator: wallet.publicKey,
+                        moai,
+                        meme: result.winner,
+                        leaderboard: getLeaderboardAddress(
+                            moai,
+                            moaiState.epoch.toNumber(),
+                        ),
+                    })
+                    .rpc({ commitment: 'confirmed' });
+                assert.fail('a paid out meme should not be banned');
+            } catch (e) {
+                assert.include(e.toString(), 'EpochClosed');
+            }
+        });
+
+        it('rejects end epoch before the window closes', async () => {
+            const moaiState = await program.account.moai.fetch(moai);
+            const nextStart = moaiState.epochEndTs.toNumber();
+            try {
+                await program.methods
+                    .endEpoch(
+                        {
+                            startTs: new BN(nextStart),
+                            submissionEndTs: new BN(nextStart + 60),
+                            endTs: new BN(nextStart + 90),
+                        },
+                        { linear: {} },
+                    )
+                    .accounts({
+                        payer: wallet.publicKey,
+                        moai,
+                        epochResult: getEpochResultAddress(
+                            moai,
+                            moaiState.epoch.toNumber(),
+                        ),
+                        leaderboard: getLeaderboardAddress(
+                            moai,
+                            moaiState.epoch.toNumber(),
+                        ),
+                        nextLeaderboard: getLeaderboardAddress(
+                            moai,
+                            moaiState.epoch.toNumber() + 1,
+                        ),
+                        rockMint: rockMint.publicKey,
+                        escrowAccount,
+                        systemProgram: SystemProgram.programId,
+                    })
+                    .rpc({ commitment: 'confirmed' });
+                assert.fail('end epoch should fail while voting is open');
+            } catch (e) {
+                assert.include(e.toString(), 'EpochNotEnded');
+            }
+        });
+    });
+
+    describe('quadratic voting', () => {
+        const voter = Keypair.generate();
+        const voterSpending = Keypair.generate();
+        const voterSpending2 = Keypair.generate();
+        const voterRockAccount = getAssociatedTokenAddressSync(
+            rockMint.publicKey,
+            voter.publicKey,
+        );
+        const voterMoaiAccount = getAssociatedTokenAddressSync(
+            moaiMint.publicKey,
+            voter.publicKey,
+        );
+        const voterSession = getSessionAddress(moai, voter.publicKey);
+        let meme: PublicKey;
+        let epoch: number;
+
+        const mintRock = (spending: Keypair, amount: number) =>
+            program.methods
+                .mintRock(new BN(amount))
+                .accounts({
+                    user: voter.publicKey,
+                    userSpending: spending.publicKey,
+                    moai,
+                    rockMint: rockMint.publicKey,
+                    moaiMint: moaiMint.publicKey,
+                    userRockAccount: voterRockAccount,
+                    userMoaiAccount: voterMoaiAccount,
+                    escrowAccount,
+                    userInfo: getUserInfoAddress(spending.publicKey, moai),
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                    memoProgram: SPL_MEMO,
+                    rent: SYSVAR_RENT_PUBKEY,
+                })
+                .signers([voter, spending])
+                .rpc({ commitment: 'confirmed' });
+
+        const createSession = async (spending: Keypair) =>
+            program.methods
+                .createSession(
+                    new BN((await connection.getSlot()) + 10_000),
+                    new BN(16),
+                    IX_CREATE_MEME | IX_VOTE,
+                )
+                .accounts({
+                    user: voter.publicKey,
+                    userSpending: spending.publicKey,
+                    moai,
+                    session: voterSession,
+                    rockMint: rockMint.publicKey,
+                    userRockAccount: voterRockAccount,
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([voter])
+                .rpc({ commitment: 'confirmed' });
+
+        const spendAccounts = (spending: Keypair) => ({
+            userSpending: spending.publicKey,
+            meme,
+            moai,
+            rockMint: rockMint.publicKey,
+            moaiMint: moaiMint.publicKey,
+            leaderboard: getLeaderboardAddress(moai, epoch),
+            userInfo: getUserInfoAddress(spending.publicKey, moai),
+            session: voterSession,
+            userRockAccount: voterRockAccount,
+            userMoaiAccount: voterMoaiAccount,
+            memeRockAccount: getAssociatedTokenAddressSync(
+                rockMint.publicKey,
+                meme,
+                true,
+            ),
+            ownerVote: getVoteAddress(voter.publicKey, meme),
+            tokenProgram: TOKEN_PROGRAM_ID,
+            associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+            memoProgram: SPL_MEMO,
+            systemProgram: SystemProgram.programId,
+            rent: SYSVAR_RENT_PUBKEY,
+        });
+
+        const rockBalance = async () =>
+            (await getAccount(connection, voterRockAccount)).amount;
+
+        before(async () => {
+            const moaiState = await program.account.moai.fetch(moai);
+            assert.deepEqual(moaiState.votingMode, { quadratic: {} });
+            epoch = moaiState.epoch.toNumber();
+
+            await sendAndConfirmTransaction(
+                connection,
+                new Transaction().add(
+                    SystemProgram.transfer({
+                        fromPubkey: testWallet.publicKey,
+                        toPubkey: voter.publicKey,
+                        lamports: LAMPORTS_PER_SOL * 3,
+                    }),
+                ),
+                [testWallet],
+            );
+            await mintRock(voterSpending, 16);
+            await createSession(voterSpending);
+
+            meme = await getNextMemeAddress();
+            await program.methods
+                .createMeme(
+                    'quadratic',
+                    'https://gateway.irys.xyz/quadratic',
+                    Array(32).fill(1),
+                )
+                .accounts(spendAccounts(voterSpending))
+                .signers([voterSpending])
+                .rpc({ commitment: 'confirmed' });
+        });
+
+        it('charges the quadratic cost of new votes', async () => {
+            const rockBefore = await rockBalance();
+
+            await program.methods
+                .vote(new BN(2))
+                .accounts(spendAccounts(voterSpending))
+                .signers([voterSpending])
+                .rpc({ commitment: 'confirmed' });
+
+            // The creation vote is the first, so 2 more cost (1 + 2)^2 - 1^2
+            assert.equal(rockBefore - (await rockBalance()), BigInt(8));
+            const voteState = await program.account.voteStatus.fetch(
+                getVoteAddress(voter.publicKey, meme),
+            );
+            assert.equal(voteState.count.toNumber(), 3);
+        });
+
+        it('refunds the quadratic cost of the last votes', async () => {
+            const rockBefore = await rockBalance();
+
+            await program.methods
+                .unvote(new BN(1))
+                .accounts({
+                    user: voter.publicKey,
+                    userSpending: voterSpending.publicKey,
+                    meme,
+                    moai,
+                    rockMint: rockMint.publicKey,
+                    moaiMint: moaiMint.publicKey,
+                    leaderboard: getLeaderboardAddress(moai, epoch),
+                    userInfo: getUserInfoAddress(voterSpending.publicKey, moai),
+                    userRockAccount: voterRockAccount,
+                    userMoaiAccount: voterMoaiAccount,
+                    memeRockAccount: getAssociatedTokenAddressSync(
+                        rockMint.publicKey,
+                        meme,
+                        true,
+                    ),
+                    ownerVote: getVoteAddress(voter.publicKey, meme),
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    memoProgram: SPL_MEMO,
+                })
+                .signers([voter])
+                .rpc({ commitment: 'confirmed' });
+
+            // Dropping from 3 to 2 votes refunds 3^2 - 2^2
+            assert.equal((await rockBalance()) - rockBefore, BigInt(5));
+        });
+
+        it('prices votes from every spending wallet together', async () => {
+            await mintRock(voterSpending2, 1);
+            await createSession(voterSpending2);
+            const rockBefore = await rockBalance();
+
+            await program.methods
+                .vote(new BN(1))
+                .accounts(spendAccounts(voterSpending2))
+                .signers([voterSpending2])
+                .rpc({ commitment: 'confirmed' });
+
+            // The owner already holds 2 votes, a new key still pays 3^2 - 2^2
+            assert.equal(rockBefore - (await rockBalance()), BigInt(5));
+            const voteState = await program.account.voteStatus.fetch(
+                getVoteAddress(voter.publicKey, meme),
+            );
+            assert.equal(voteState.count.toNumber(), 3);
+        });
+    });
+
+    describe('graduated meme pool', () => {
+        const whirlpoolCtx = WhirlpoolContext.withProvider(
+            provider,
+            ORCA_WHIRLPOOL_PROGRAM_ID,
+        );
+        const whirlpoolClient = buildWhirlpoolClient(whirlpoolCtx);
+        const tickSpacing = 128;
+
+        let meme: PublicKey;
+        let memeMint: PublicKey;
+        let whirlpool: PublicKey;
+        let memePool: PublicKey;
+        let mintA: PublicKey;
+        let mintB: PublicKey;
+        const tokenVaultA = Keypair.generate();
+        const tokenVaultB = Keypair.generate();
+        const positionMint = Keypair.generate();
+        const position = PDAUtil.getPosition(
+            ORCA_WHIRLPOOL_PROGRAM_ID,
+            positionMint.publicKey,
+        ).publicKey;
+
+        before(async () => {
+            const result = await program.account.epochResult.fetch(
+                getEpochResultAddress(moai, 0),
+            );
+            meme = result.winner;
+            memeMint = getMemeMintAddress(meme);
+            memePool = getMemePoolAddress(meme);
+            [mintA, mintB] = PoolUtil.orderMints(memeMint, SOL.mint).map(
+                mint => new PublicKey(mint),
+            );
+            whirlpool = PDAUtil.getWhirlpool(
+                ORCA_WHIRLPOOL_PROGRAM_ID,
+                ORCA_WHIRLPOOLS_CONFIG,
+                mintA,
+                mintB,
+                tickSpacing,
+            ).publicKey;
+        });
+
+        it('init meme pool', async () => {
+            // 10,000,000 meme tokens per SOL
+            const memePerSol = new Decimal(10_000_000);
+            const price = mintA.equals(SOL.mint)
+                ? memePerSol
+                : new Decimal(1).div(memePerSol);
+            const initialSqrtPrice = PriceMath.priceToSqrtPriceX64(price, 9, 9);
+            const [tickLowerIndex, tickUpperIndex] =
+                TickUtil.getFullRangeTickIndex(tickSpacing);
+
+            const signature = await program.methods
+                .initMemePool(tickSpacing, initialSqrtPrice)
+                .accounts({
+                    authority: wallet.publicKey,
+                    moai,
+                    meme,
+                    memePool,
+                    whirlpoolProgram: ORCA_WHIRLPOOL_PROGRAM_ID,
+                    whirlpoolsConfig: ORCA_WHIRLPOOLS_CONFIG,
+                    tokenMintA: mintA,
+                    tokenMintB: mintB,
+                    whirlpool,
+                    tokenVaultA: tokenVaultA.publicKey,
+                    tokenVaultB: tokenVaultB.publicKey,
+                    feeTier: PDAUtil.getFeeTier(
+                        ORCA_WHIRLPOOL_PROGRAM_ID,
+                        ORCA_WHIRLPOOLS_CONFIG,
+                        tickSpacing,
+                    ).publicKey,
+                    tickArrayLower: PDAUtil.getTickArrayFromTickIndex(
+                        tickLowerIndex,
+                        tickSpacing,
+                        whirlpool,
+                        ORCA_WHIRLPOOL_PROGRAM_ID,
+                    ).publicKey,
+                    tickArrayUpper: PDAUtil.getTickArrayFromTickIndex(
+                        tickUpperIndex,
+                        tickSpacing,
+                        whirlpool,
+                        ORCA_WHIRLPOOL_PROGRAM_ID,
+                    ).publicKey,
+                    tokenProgram: TOKEN_PROGRAM_ID,
+                    systemProgram: SystemProgram.programId,
+                    rent: SYSVAR_RENT_PUBKEY,
+                })
+                .signers([tokenVaultA, tokenVaultB])
+                .rpc({ commitment: 'confirmed' });
+            console.log('init meme pool signature: ', signature);
+
+            const memePoolState = await program.account.memePool.fetch(
+                memePool,
+            );
+            assert.isTrue(memePoolState.whirlpool.equals(whirlpool));
+            assert.equal(memePoolState.wsolIsTokenA, mintA.equals(SOL.mint));
+            assert.isNull(memePoolState.position);
+        });
+
+        it('seed meme pool', async () => {
+            const pool = await whirlpoolClient.getPool(whirlpool, IGNORE_CACHE);
+            const [tickLowerIndex, tickUpperIndex] =
+                TickUtil.getFullRangeTickIndex(tickSpacing);
+            // Deposit half of the liquidity share set aside from the prize
+            const { liquidityBudget } = await program.account.moai.fetch(moai);
+            const quote = increaseLiquidityQuoteByInputToken(
+                SOL.mint,
+                DecimalUtil.fromBN(liquidityBudget.divn(2), 9),
+                tickLowerIndex,
+                tickUpperIndex,
+                Percentage.fromFraction(0, 1000),
+                pool,
+            );
+            const memePoolState = await program.account.memePool.fetch(
+                memePool,
+            );
+            const seedAccounts = {
+                authority: wallet.publicKey,
+                moai,
+                memePool,
+                whirlpoolProgram: ORCA_WHIRLPOOL_PROGRAM_ID,
+                whirlpool,
+                position,
+                positionMint: positionMint.publicKey,
+                positionTokenAccount: getAssociatedTokenAddressSync(
+                    positionMint.publicKey,
+                    moai,
+                    true,
+                ),
+                escrowAccount,
+                moaiMemeAccount: getAssociatedTokenAddressSync(
+                    memeMint,
+                    moai,
+                    true,
+                ),
+                tokenVaultA: pool.getData().tokenVaultA,
+                tokenVaultB: pool.getData().tokenVaultB,
+                tickArrayLower: memePoolState.tickArrayLower,
+                tickArrayUpper: memePoolState.tickArrayUpper,
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+                rent: SYSVAR_RENT_PUBKEY,
+            };
+
+            const overBudget = liquidityBudget.addn(1);
+            try {
+                await program.methods
+                    .seedMemePool(
+                        quote.liquidityAmount,
+                        mintA.equals(SOL.mint) ? overBudget : quote.tokenMaxA,
+                        mintA.equals(SOL.mint) ? quote.tokenMaxB : overBudget,
+                    )
+                    .accounts(seedAccounts)
+                    .signers([positionMint])
+                    .rpc();
+                assert.fail('expected LiquidityBudgetExceeded');
+            } catch (e) {
+                assert.include(e.toString(), 'LiquidityBudgetExceeded');
+            }
+
+            const escrowBefore = await getAccount(connection, escrowAccount);
+            const signature = await program.methods
+                .seedMemePool(
+                    quote.liquidityAmount,
+                    quote.tokenMaxA,
+                    quote.tokenMaxB,
+                )
+                .accounts(seedAccounts)
+                .signers([positionMint])
+                .rpc({ commitment: 'confirmed' });
+            console.log('seed meme pool signature: ', signature);
+
+            // The budget only shrinks by the WSOL actually deposited
+            const escrowAfter = await getAccount(connection, escrowAccount);
+            const moaiState = await program.account.moai.fetch(moai);
+            const deposited = escrowBefore.amount - escrowAfter.amount;
+            assert.isTrue(deposited > BigInt(0));
+            assert.equal(
+                moaiState.liquidityBudget.toString(),
+                liquidityBudget.sub(new BN(deposited.toString())).toString(),
+            );
+
+            const seeded = await program.account.memePool.fetch(memePool);
+            assert.isTrue(seeded.position.equals(position));
+            const positionData = await whirlpoolCtx.fetcher.getPosition(
+                position,
+                IGNORE_CACHE,
+            );
+            assert.isTrue(positionData.liquidity.eq(quote.liquidityAmount));
+            assert.equal(positionData.tickLowerIndex, tickLowerIndex);
+            assert.equal(positionData.tickUpperIndex, tickUpperIndex);
+        });
+
+        // Trade both ways so the position earns fees in both tokens
+        const swap = async (inputMint: PublicKey, amount: BN) => {
+            const pool = await whirlpoolClient.getPool(whirlpool, IGNORE_CACHE);
+            const quote = await swapQuoteByInputToken(
+                pool,
+                inputMint,
+                amount,
+                Percentage.fromFraction(1, 100),
+                ORCA_WHIRLPOOL_PROGRAM_ID,
+                whirlpoolCtx.fetcher,
+                IGNORE_CACHE,
+            );
+            await (await pool.swap(quote)).buildAndExecute();
+            return quote.estimatedAmountOut;
+        };
+        const swapBothWays = async () => {
+            const memeOut = await swap(
+                SOL.mint,
+                new BN(LAMPORTS_PER_SOL / 1000),
+            );
+            await swap(memeMint, memeOut);
+        };
+
+        const harvestAccounts = async () => {
+            const pool = await whirlpoolClient.getPool(whirlpool, IGNORE_CACHE);
+            const memePoolState = await program.account.memePool.fetch(
+                memePool,
+            );
+            const newWalletAccount = (mint: PublicKey) =>
+                createAccount(
+                    connection,
+                    testWallet,
+                    mint,
+                    wallet.publicKey,
+                    Keypair.generate(),
+                );
+            return {
+                payer: wallet.publicKey,
+                moai,
+                meme,
+                memePool,
+                whirlpoolProgram: ORCA_WHIRLPOOL_PROGRAM_ID,
+                whirlpool,
+                position,
+                positionTokenAccount: getAssociatedTokenAddressSync(
+                    positionMint.publicKey,
+                    moai,
+                    true,
+                ),
+                tickArrayLower: memePoolState.tickArrayLower,
+                tickArrayUpper: memePoolState.tickArrayUpper,
+                tokenVaultA: pool.getData().tokenVaultA,
+                tokenVaultB: pool.getData().tokenVaultB,
+                wsolMint: SOL.mint,
+                memeMint,
+                escrowAccount,
+                moaiMemeAccount: getAssociatedTokenAddressSync(
+                    memeMint,
+                    moai,
+                    true,
+                ),
+                creator: userSpending.publicKey,
+                creatorWsolAccount: getAssociatedTokenAddressSync(
+                    SOL.mint,
+                    userSpending.publicKey,
+                ),
+                creatorMemeAccount: getAssociatedTokenAddressSync(
+                    memeMint,
+                    userSpending.publicKey,
+                ),
+                treasuryWsolAccount: await newWalletAccount(SOL.mint),
+                treasuryMemeAccount: await newWalletAccount(memeMint),
+                buybackWsolAccount: await newWalletAccount(SOL.mint),
+                buybackMemeAccount: await newWalletAccount(memeMint),
+                tokenProgram: TOKEN_PROGRAM_ID,
+                associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+                systemProgram: SystemProgram.programId,
+            };
+        };
+
+        // Balance of a token account, zero when it does not exist
+        const balanceOf = (account: PublicKey) =>
+            getAccount(connection, account).then(
+                ({ amount }) => amount,
+                () => BigInt(0),
+            );
+
+        // Balance change of each of `recipients` across a harvest
+        const harvest = async (
+            accounts: Awaited<ReturnType<typeof harvestAccounts>>,
+        ) => {
+            const recipients = [
+                accounts.creatorWsolAccount,
+                accounts.treasuryWsolAccount,
+                accounts.buybackWsolAccount,
+                accounts.creatorMemeAccount,
+                accounts.treasuryMemeAccount,
+                accounts.buybackMemeAccount,
+            ];
+            const before = await Promise.all(recipients.map(balanceOf));
+            const signature = await program.methods
+                .harvestLpFees()
+                .accounts(accounts)
+                .rpc({ commitment: 'confirmed' });
+            console.log('harvest lp fees signature: ', signature);
+            const after = await Promise.all(recipients.map(balanceOf));
+            const deltas = after.map((amount, i) => amount - before[i]);
+            // [creator, treasury, buyback] for WSOL, then for the meme token
+            return [deltas.slice(0, 3), deltas.slice(3)];
+        };
+
+        const share = (fees: bigint, bps: number) =>
+            (fees * BigInt(bps)) / BigInt(10000);
+
+        it('harvest lp fees', async () => {
+            await swapBothWays();
+            const accounts = await harvestAccounts();
+
+            const splits = await harvest(accounts);
+            for (const [creator, treasury, buyback] of splits) {
+                const fees = creator + treasury + buyback;
+                assert.isTrue(fees > BigInt(0));
+                assert.equal(
+                    creator,
+                    share(fees, moaiConfig.lpCreatorShareBps),
+                );
+                assert.equal(
+                    treasury,
+                    share(fees, moaiConfig.lpTreasuryShareBps),
+                );
+            }
+
+            // The position stays with the moai after harvesting
+            const positionTokenAccount = await getAccount(
+                connection,
+                accounts.positionTokenAccount,
+            );
+            assert.equal(positionTokenAccount.amount.toString(), '1');
+        });
+
+        it('harvest lp fees reopens a closed creator account', async () => {
+            await swapBothWays();
+            const accounts = await harvestAccounts();
+            const creatorMeme = await getAccount(
+                connection,
+                accounts.creatorMemeAccount,
+            );
+            await burn(
+                connection,
+                testWallet,
+                creatorMeme.address,
+                memeMint,
+                userSpending,
+                creatorMeme.amount,
+            );
+            await closeAccount(
+                connection,
+                testWallet,
+                creatorMeme.address,
+                userSpending.publicKey,
+                userSpending,
+            );
+
+            // The creator still gets their share in a fresh account
+            const [, memeToken] = await harvest(accounts);
+            const memeFees = memeToken[0] + memeToken[1] + memeToken[2];
+            assert.isTrue(memeFees > BigInt(0));
+            assert.equal(
+                memeToken[0],
+                share(memeFees, moaiConfig.lpCreatorShareBps),
+            );
+        });
+    });
+
+    describe('renounced authority', () => {
+        it('rejects renouncing while paused', async () => {
+            await program.methods
+                .setPause(true, 0)
+                .accounts({ authority: wallet.publicKey, moai })
+                .rpc({ commitment: 'confirmed' });
+
+            try {
+                await program.methods
+                    .renounceAuthority()
+                    .accounts({ authority: wallet.publicKey, moai })
+                    .rpc({ commitment: 'confirmed' });
+                assert.fail('renounce should fail while paused');
+            } catch (e) {
+                assert.include(e.toString(), 'RenounceWhilePaused');
+            } finally {
+                await program.methods
+                    .setPause(false, 0)
+                    .accounts({ authority: wallet.publicKey, moai })
+                    .rpc({ commitment: 'confirmed' });
+            }
+        });
+
+        it('ends an epoch after renouncing', async () => {
+            const signature = await program.methods
+                .renounceAuthority()
+                .accounts({ authority: wallet.publicKey, moai })
+                .rpc({ commitment: 'confirmed' });
+            console.log('renounce authority signature: ', signature);
+
+            const moaiState = await program.account.moai.fetch(moai);
+            assert.isFalse(moaiState.authorityValid);
+            assert.isTrue(moaiState.liquidityBudget.isZero());
+            const epoch = moaiState.epoch.toNumber();
+
+            const waitMs =
+                (moaiState.epochEndTs.toNumber() + 2) * 1000 - Date.now();
+            if (waitMs > 0) {
+                await sleep(waitMs);
+            }
+
+            // Any payer may end the epoch, the window it passes is ignored
+            const payer = Keypair.generate();
+            await sendAndConfirmTransaction(
+                connection,
+                new Transaction().add(
+                    SystemProgram.transfer({
+                        fromPubkey: testWallet.publicKey,
+                        toPubkey: payer.publicKey,
+                        lamports: LAMPORTS_PER_SOL,
+                    }),
+                ),
+                [testWallet],
+            );
+            await program.methods
+                .endEpoch(
+                    {
+                        startTs: new BN(0),
+                        submissionEndTs: new BN(1),
+                        endTs: new BN(2),
+                    },
+                    { linear: {} },
+                )
+                .accounts({
+                    payer: payer.publicKey,
+                    moai,
+                    epochResult: getEpochResultAddress(moai, epoch),
+                    leaderboard: getLeaderboardAddress(moai, epoch),
+                    nextLeaderboard: getLeaderboardAddress(moai, epoch + 1),
+                    rockMint: rockMint.publicKey,
+                    escrowAccount,
+                    systemProgram: SystemProgram.programId,
+                })
+                .signers([payer])
+                .rpc({ commitment: 'confirmed' });
+
+            const nextMoaiState = await program.account.moai.fetch(moai);
+            assert.equal(nextMoaiState.epoch.toNumber(), epoch + 1);
+            assert.deepEqual(nextMoaiState.votingMode, moaiState.votingMode);
+            assert.isAtLeast(
+                nextMoaiState.epochStartTs.toNumber(),
+                moaiState.epochEndTs.toNumber(),
+            );
+            const duration = (state: typeof moaiState) =>
+                state.epochEndTs.sub(state.epochStartTs).toNumber();
+            assert.equal(duration(nextMoaiState), duration(moaiState));
         });
     });
 });
@@ -8,6 +8,17 @@ const MOAI = 'moai';
 const MEME = 'meme';
 const VOTE = 'vote';
 const USER = 'user';
+const EPOCH = 'epoch';
+const SESSION = 'session';
+const LEADERBOARD = 'leaderboard';
+const MEME_MINT = 'meme_mint';
+const MEME_POOL = 'meme_pool';
+const METADATA = 'metadata';
+const REPORT = 'report';
+
+export const TOKEN_METADATA_PROGRAM_ID = new PublicKey(
+    'metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s',
+);
 const program = anchor.workspace.Moai as Program<Moai>;
 
 export const getMoaiAddress = (authority: PublicKey) => {
@@ -18,19 +29,28 @@ export const getMoaiAddress = (authority: PublicKey) => {
     return address;
 };
 
-export const getMemeAddress = (index: string) => {
+export const getMemeAddress = (
+    moai: PublicKey,
+    epoch: number,
+    id: number,
+) => {
     const [address, _] = PublicKey.findProgramAddressSync(
-        [anchor.utils.bytes.utf8.encode(MEME), Buffer.from(index)],
+        [
+            anchor.utils.bytes.utf8.encode(MEME),
+            moai.toBuffer(),
+            new anchor.BN(epoch).toArrayLike(Buffer, 'le', 8),
+            new anchor.BN(id).toArrayLike(Buffer, 'le', 8),
+        ],
         program.programId,
     );
     return address;
 };
 
-export const getVoteAddress = (userSpending: PublicKey, meme: PublicKey) => {
+export const getVoteAddress = (owner: PublicKey, meme: PublicKey) => {
     const [address, _] = PublicKey.findProgramAddressSync(
         [
             anchor.utils.bytes.utf8.encode(VOTE),
-            userSpending.toBuffer(),
+            owner.toBuffer(),
             meme.toBuffer(),
         ],
         program.programId,
@@ -52,3 +72,79 @@ export const getUserInfoAddress = (
     );
     return address;
 };
+
+export const getEpochResultAddress = (moai: PublicKey, epoch: number) => {
+    const [address, _] = PublicKey.findProgramAddressSync(
+        [
+            anchor.utils.bytes.utf8.encode(EPOCH),
+            moai.toBuffer(),
+            new anchor.BN(epoch).toArrayLike(Buffer, 'le', 8),
+        ],
+        program.programId,
+    );
+    return address;
+};
+
+export const getSessionAddress = (moai: PublicKey, owner: PublicKey) => {
+    const [address, _] = PublicKey.findProgramAddressSync(
+        [
+            anchor.utils.bytes.utf8.encode(SESSION),
+            moai.toBuffer(),
+            owner.toBuffer(),
+        ],
+        program.programId,
+    );
+    return address;
+};
+
+export const getLeaderboardAddress = (moai: PublicKey, epoch: number) => {
+    const [address, _] = PublicKey.findProgramAddressSync(
+        [
+            anchor.utils.bytes.utf8.encode(LEADERBOARD),
+            moai.toBuffer(),
+            new anchor.BN(epoch).toArrayLike(Buffer, 'le', 8),
+        ],
+        program.programId,
+    );
+    return address;
+};
+
+export const getMemeMintAddress = (meme: PublicKey) => {
+    const [address, _] = PublicKey.findProgramAddressSync(
+        [anchor.utils.bytes.utf8.encode(MEME_MINT), meme.toBuffer()],
+        program.programId,
+    );
+    return address;
+};
+
+export const getMetadataAddress = (mint: PublicKey) => {
+    const [address, _] = PublicKey.findProgramAddressSync(
+        [
+            anchor.utils.bytes.utf8.encode(METADATA),
+            TOKEN_METADATA_PROGRAM_ID.toBuffer(),
+            mint.toBuffer(),
+        ],
+        TOKEN_METADATA_PROGRAM_ID,
+    );
+    return address;
+};
+
+export const getMemePoolAddress = (meme: PublicKey) => {
+    const [address, _] = PublicKey.findProgramAddressSync(
+        [anchor.utils.bytes.utf8.encode(MEME_POOL), meme.toBuffer()],
+        program.programId,
+    );
+    return address;
+};
+
+export const getReportAddress = (meme: PublicKey, reporter: PublicKey) => {
+    const [address, _] = PublicKey.findProgramAddressSync(
+        [
+            anchor.utils.bytes.utf8.encode(REPORT),
+            meme.toBuffer(),
+            reporter.toBuffer(),
+        ],
+        program.programId,
+    );
+    return address;
+};